@@ -0,0 +1,271 @@
+//! Plays seeded games headless with a bot and reports how they went.
+//!
+//! `bench [--games <n>] [--seed <first seed>] [--strategy solver|hint|first|random] [--size <size>] [--mode <mode>]
+//! [--max-moves <n>] [--csv]`
+//!
+//! Game `i` is dealt from seed `first seed + i`, so two runs with the same arguments play the very same games.
+//! `--csv` prints one row per game instead of the summary table. Puzzles aren't dealt from seeds, so they can't be benched.
+
+use std::{fmt, str::FromStr};
+
+use koala_kombo::{
+	koala_kombo::{BoardSize, KoalaKombo, Move, Shape},
+	mode::GameMode,
+	rng::GameRng,
+	solver::Solver,
+};
+use rand::seq::IndexedRandom;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Strategy {
+	/// Plans the whole tray with [`Solver`].
+	Solver,
+	/// Plays the suggestion of [`KoalaKombo::hint`] one piece at a time.
+	Hint,
+	/// Plays the first piece that fits at the first spot it fits, scanning row by row.
+	First,
+	/// Plays a random piece at a random spot it fits.
+	Random,
+}
+
+impl Strategy {
+	const ALL: &[Strategy] = &[Strategy::Solver, Strategy::Hint, Strategy::First, Strategy::Random];
+
+	fn name(self) -> &'static str {
+		match self {
+			Strategy::Solver => "solver",
+			Strategy::Hint => "hint",
+			Strategy::First => "first",
+			Strategy::Random => "random",
+		}
+	}
+
+	/// Picks the next moves to play, empty once nothing fits.
+	fn next_moves(self, game: &KoalaKombo, solver: &Solver, rng: &mut GameRng) -> Vec<Move> {
+		let fitting = || {
+			(0..game.pieces.len())
+				.flat_map(|piece_idx| game.size().coords().map(move |coord| Move::new(piece_idx, coord)))
+				.filter(|mv| game.check_placement(mv.piece_idx, mv.coord).is_ok())
+		};
+
+		match self {
+			Strategy::Solver => solver.plan(game).map(|plan| plan.moves).unwrap_or_default(),
+			Strategy::Hint => match game.hint() {
+				Some(hint) => {
+					let place = Move {
+						turns: hint.turns,
+						..Move::new(hint.piece_idx, hint.coord)
+					};
+					if hint.hold {
+						vec![Move::hold(hint.piece_idx), place]
+					} else {
+						vec![place]
+					}
+				},
+				None => Vec::new(),
+			},
+			Strategy::First => fitting().take(1).collect(),
+			Strategy::Random => fitting().collect::<Vec<_>>().choose(rng).copied().into_iter().collect(),
+		}
+	}
+}
+
+impl fmt::Display for Strategy {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.name())
+	}
+}
+
+impl FromStr for Strategy {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Strategy::ALL.iter().copied().find(|strategy| strategy.name() == s).ok_or_else(|| format!("unknown strategy `{s}`"))
+	}
+}
+
+/// How a single game went.
+#[derive(Debug, Clone)]
+struct GameRecord {
+	seed: u64,
+	score: u32,
+	moves: usize,
+	lines: usize,
+	/// Shapes left in the tray when nothing fit anymore, empty if the game hit the move limit.
+	game_over: Vec<Shape>,
+}
+
+fn play(strategy: Strategy, solver: &Solver, mut game: KoalaKombo, max_moves: usize) -> GameRecord {
+	// The bot's own choices come from a separate RNG, so they don't change the dealt pieces
+	let mut rng = GameRng::new(game.seed().rotate_left(32));
+	let mut moves = 0;
+	let mut lines = 0;
+
+	'game: while moves < max_moves && !game.is_game_over() {
+		let planned = strategy.next_moves(&game, solver, &mut rng);
+		if planned.is_empty() {
+			break;
+		}
+		for mv in planned {
+			let Ok(outcome) = game.play_move(mv) else {
+				break 'game;
+			};
+			moves += 1;
+			lines += outcome.map_or(0, |outcome| outcome.clear.lines());
+		}
+	}
+
+	let game_over = match game.is_game_over() {
+		true => game.pieces.iter().filter(|piece| !piece.used).map(|piece| piece.shape).collect(),
+		false => Vec::new(),
+	};
+	GameRecord {
+		seed: game.seed(),
+		score: game.score,
+		moves,
+		lines,
+		game_over,
+	}
+}
+
+/// Mean, extremes and percentiles of a metric over all games.
+struct Summary {
+	mean: f64,
+	values: Vec<u64>,
+}
+
+impl Summary {
+	fn new(values: impl IntoIterator<Item = u64>) -> Self {
+		let mut values = values.into_iter().collect::<Vec<_>>();
+		values.sort_unstable();
+		let mean = values.iter().sum::<u64>() as f64 / values.len().max(1) as f64;
+		Self { mean, values }
+	}
+
+	/// Nearest-rank percentile, `p` in `0..=100`.
+	fn percentile(&self, p: usize) -> u64 {
+		let rank = (p * self.values.len()).div_ceil(100).max(1);
+		self.values.get(rank - 1).copied().unwrap_or_default()
+	}
+}
+
+fn print_table(strategy: Strategy, records: &[GameRecord]) {
+	println!("{} games, strategy {strategy}", records.len());
+	println!();
+	println!("{:<8}{:>10}{:>8}{:>8}{:>8}{:>8}{:>8}", "", "mean", "min", "p10", "p50", "p90", "max");
+	type Metric = fn(&GameRecord) -> u64;
+	let metrics: [(&str, Metric); 3] = [
+		("score", |record| record.score as u64),
+		("moves", |record| record.moves as u64),
+		("lines", |record| record.lines as u64),
+	];
+	for (name, metric) in metrics {
+		let summary = Summary::new(records.iter().map(metric));
+		println!(
+			"{:<8}{:>10.1}{:>8}{:>8}{:>8}{:>8}{:>8}",
+			name,
+			summary.mean,
+			summary.percentile(0),
+			summary.percentile(10),
+			summary.percentile(50),
+			summary.percentile(90),
+			summary.percentile(100)
+		);
+	}
+
+	let ended = records.iter().filter(|record| !record.game_over.is_empty()).count();
+	println!();
+	println!("Shapes left in the tray at game over ({ended} games ended, {} hit the move limit)", records.len() - ended);
+	let mut counts = Shape::ALL
+		.iter()
+		.map(|&shape| {
+			let count = records.iter().filter(|record| record.game_over.contains(&shape)).count();
+			(shape, count)
+		})
+		.filter(|&(_, count)| count > 0)
+		.collect::<Vec<_>>();
+	counts.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+	for (shape, count) in counts {
+		println!("{:<16}{:>8}{:>8.1}%", format!("{shape:?}"), count, count as f64 * 100.0 / ended.max(1) as f64);
+	}
+}
+
+fn print_csv(records: &[GameRecord]) {
+	println!("seed,score,moves,lines,game_over");
+	for record in records {
+		let shapes = record.game_over.iter().map(|shape| format!("{shape:?}")).collect::<Vec<_>>().join(";");
+		println!("{},{},{},{},{}", record.seed, record.score, record.moves, record.lines, shapes);
+	}
+}
+
+/// Parses the value given after `name`, `None` if the option isn't given. Exits on a missing or invalid value.
+fn arg<T: FromStr<Err: fmt::Display>>(name: &str) -> Option<T> {
+	let mut args = std::env::args().skip_while(|arg| arg != name);
+	args.next()?;
+	let Some(value) = args.next() else {
+		eprintln!("bench: {name} needs a value");
+		std::process::exit(1);
+	};
+	match value.parse() {
+		Ok(value) => Some(value),
+		Err(err) => {
+			eprintln!("bench: invalid {name} `{value}`: {err}");
+			std::process::exit(1);
+		},
+	}
+}
+
+fn main() {
+	let games = arg("--games").unwrap_or(100u64);
+	let first_seed = arg("--seed").unwrap_or(0u64);
+	let strategy = arg("--strategy").unwrap_or(Strategy::Solver);
+	let size = arg::<BoardSize>("--size").unwrap_or_default();
+	let mode = arg::<GameMode>("--mode").unwrap_or_default();
+	if mode == GameMode::Puzzle {
+		eprintln!("bench: puzzles aren't dealt from seeds, pick another mode");
+		std::process::exit(1);
+	}
+	let max_moves = arg("--max-moves").unwrap_or(10_000);
+	let csv = std::env::args().any(|arg| arg == "--csv");
+
+	let solver = Solver::default();
+	let records = (0..games)
+		.map(|i| {
+			let game = mode.builder().with_size(size).with_seed(first_seed.wrapping_add(i)).build();
+			play(strategy, &solver, game, max_moves)
+		})
+		.collect::<Vec<_>>();
+
+	if csv {
+		print_csv(&records);
+	} else {
+		print_table(strategy, &records);
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn percentile_test() {
+		let summary = Summary::new([5, 1, 4, 2, 3]);
+		assert_eq!(summary.mean, 3.0);
+		assert_eq!(summary.percentile(0), 1);
+		assert_eq!(summary.percentile(50), 3);
+		assert_eq!(summary.percentile(100), 5);
+	}
+
+	#[test]
+	fn play_test() {
+		let game = KoalaKombo::builder().with_size(BoardSize::square(5)).with_seed(1).build();
+		let record = play(Strategy::First, &Solver::default(), game, 1_000);
+		assert!(record.moves > 0);
+		assert!(!record.game_over.is_empty());
+		assert_eq!(record.seed, 1);
+
+		// Replaying the same seed plays the same game
+		let game = KoalaKombo::builder().with_size(BoardSize::square(5)).with_seed(1).build();
+		assert_eq!(play(Strategy::First, &Solver::default(), game, 1_000).score, record.score);
+	}
+}
@@ -0,0 +1,584 @@
+//! Plays Koala Kombo in the terminal, no GPU needed.
+//!
+//! `tui [--seed <n>] [--size <size>] [--mode <mode>]`
+//!
+//! Arrow keys or `hjkl` move the cursor, `1`-`3` or tab pick a piece, enter or space places it.
+//! `t` turns the piece and `p` holds it in modes that allow it, `P` takes the held piece back,
+//! `?` shows a hint, `u` undoes, `r` redoes, `n` starts a new game and `q`, escape or Ctrl+C quits.
+//! Puzzles are only played in the game window.
+
+use std::{
+	fmt::{self, Write as _},
+	io::{self, Read, Write},
+	str::FromStr,
+	time::Instant,
+};
+
+use koala_kombo::{
+	daily,
+	date::Date,
+	koala_kombo::{BoardSize, Cell, Coord, KoalaKombo, Shape},
+	mode::GameMode,
+};
+
+const RESET: &str = "\x1b[0m";
+const FILLED: &str = "\x1b[38;5;75m";
+const EMPTY: &str = "\x1b[38;5;238m";
+const VALID: &str = "\x1b[38;5;114m";
+const INVALID: &str = "\x1b[38;5;203m";
+const CURSOR: &str = "\x1b[38;5;229m";
+const STONE: &str = "\x1b[38;5;245m";
+const ICE: &str = "\x1b[38;5;195m";
+const LOCKED: &str = "\x1b[38;5;172m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+	Up,
+	Down,
+	Left,
+	Right,
+	Enter,
+	Tab,
+	Escape,
+	Char(char),
+}
+
+/// Splits raw terminal input into keys, turning the arrow key escape sequences into arrows.
+fn parse_keys(input: &[u8]) -> Vec<Key> {
+	let mut keys = Vec::new();
+	let mut bytes = input.iter().copied().peekable();
+	while let Some(byte) = bytes.next() {
+		let key = match byte {
+			0x1b if bytes.peek() == Some(&b'[') => {
+				bytes.next();
+				match bytes.next() {
+					Some(b'A') => Key::Up,
+					Some(b'B') => Key::Down,
+					Some(b'C') => Key::Right,
+					Some(b'D') => Key::Left,
+					_ => continue,
+				}
+			},
+			0x1b => Key::Escape,
+			// Ctrl+C, which the raw terminal passes on instead of raising a signal
+			0x03 => Key::Escape,
+			b'\r' | b'\n' => Key::Enter,
+			b'\t' => Key::Tab,
+			byte if byte.is_ascii_graphic() || byte == b' ' => Key::Char(byte as char),
+			_ => continue,
+		};
+		keys.push(key);
+	}
+	keys
+}
+
+/// Draws a shape with half blocks, two rows of cells per line, bottom-aligned like the art in [`Shape`].
+fn shape_art(shape: Shape) -> Vec<String> {
+	let coords = shape.get_coords();
+	let width = coords.iter().map(|c| c.column + 1).max().unwrap_or(0);
+	let height = coords.iter().map(|c| c.row + 1).max().unwrap_or(0);
+	let offset = height % 2;
+	let filled =
+		|column: usize, row: usize| row >= offset && coords.iter().any(|c| c.column == column && c.row == row - offset);
+
+	(0..height.div_ceil(2))
+		.map(|line| {
+			(0..width)
+				.map(|column| match (filled(column, line * 2), filled(column, line * 2 + 1)) {
+					(true, true) => '█',
+					(true, false) => '▀',
+					(false, true) => '▄',
+					(false, false) => ' ',
+				})
+				.collect()
+		})
+		.collect()
+}
+
+struct Tui {
+	game: KoalaKombo,
+	seed: Option<u64>,
+	size: BoardSize,
+	mode: GameMode,
+	selected: usize,
+	cursor: Coord,
+	/// Feedback on the last action, shown under the tray.
+	message: String,
+}
+
+impl Tui {
+	fn new(seed: Option<u64>, size: BoardSize, mode: GameMode) -> Self {
+		let mut tui = Self {
+			game: KoalaKombo::new(),
+			seed,
+			// Everyone plays the daily challenge on the default board
+			size: if mode == GameMode::Daily {
+				BoardSize::default()
+			} else {
+				size
+			},
+			mode,
+			selected: 0,
+			cursor: Coord::new(0, 0),
+			message: String::new(),
+		};
+		tui.restart();
+		tui
+	}
+
+	fn restart(&mut self) {
+		let builder = self.mode.builder().with_size(self.size);
+		self.game = match self.seed {
+			// The terminal keeps no daily history, so its daily challenge is practice only
+			_ if self.mode == GameMode::Daily => daily::game(Date::today()),
+			Some(seed) => builder.with_seed(seed).build(),
+			None => builder.build(),
+		};
+		self.selected = 0;
+		self.cursor = Coord::new(0, 0);
+		self.message = String::from("New game");
+	}
+
+	/// Selects the next unused piece after `from`, wrapping around.
+	fn select_next(&mut self, from: usize) {
+		let count = self.game.pieces.len();
+		if let Some(next) = (1..=count).map(|step| (from + step) % count).find(|&i| !self.game.pieces[i].used) {
+			self.selected = next;
+		}
+	}
+
+	fn move_cursor(&mut self, dc: isize, dr: isize) {
+		let column = self.cursor.column.saturating_add_signed(dc).min(self.size.columns - 1);
+		let row = self.cursor.row.saturating_add_signed(dr).min(self.size.rows - 1);
+		self.cursor = Coord::new(column, row);
+	}
+
+	fn place(&mut self) {
+		match self.game.place_shape(self.selected, self.cursor) {
+			Ok(outcome) => {
+				self.message = match outcome.clear.lines() {
+					0 => format!("+{}", outcome.points),
+					1 => format!("+{} for 1 line", outcome.points),
+					lines => format!("+{} for {lines} lines", outcome.points),
+				};
+				if outcome.refilled {
+					self.selected = 0;
+				} else {
+					self.select_next(self.selected);
+				}
+			},
+			Err(err) => self.message = err.to_string(),
+		}
+		if self.game.is_game_over() {
+			self.show_game_over();
+		}
+	}
+
+	fn show_game_over(&mut self) {
+		self.message = format!("Game over! Final score {}. Press n for a new game or q to quit.", self.game.score);
+	}
+
+	/// Runs the clock of a timed game down by `dt` seconds, the time spent waiting for a key.
+	fn tick(&mut self, dt: f32) {
+		if self.game.clock_mut().is_some_and(|clock| clock.tick(dt)) {
+			self.show_game_over();
+		}
+	}
+
+	/// Swaps the piece in the slot with the hold slot, see [`KoalaKombo::hold_piece`].
+	fn hold(&mut self, slot: usize) {
+		if !self.game.hold_piece(slot) {
+			self.message = String::from("This piece can't be held");
+			return;
+		}
+		self.message = String::from("Held");
+		if self.game.pieces[self.selected].used {
+			self.select_next(self.selected);
+		} else {
+			self.selected = slot;
+		}
+	}
+
+	fn turn(&mut self) {
+		if !self.game.rotate_piece(self.selected) {
+			self.message = String::from("This piece can't be turned");
+		}
+	}
+
+	fn undo(&mut self) {
+		if self.game.undo() {
+			self.select_next(self.game.pieces.len() - 1);
+			self.message = String::from("Undone");
+		}
+	}
+
+	fn redo(&mut self) {
+		if self.game.redo() {
+			self.select_next(self.game.pieces.len() - 1);
+			self.message = String::from("Redone");
+		}
+	}
+
+	fn hint(&mut self) {
+		match self.game.hint() {
+			Some(hint) => {
+				self.selected = hint.piece_idx;
+				self.cursor = hint.coord;
+				self.message = hint.to_string();
+			},
+			None => self.message = String::from("Nothing fits anymore"),
+		}
+	}
+
+	/// Applies a key. Returns `false` once the player quits.
+	fn handle(&mut self, key: Key) -> bool {
+		match key {
+			Key::Char('q') | Key::Escape => return false,
+			Key::Up | Key::Char('k') | Key::Char('w') => self.move_cursor(0, -1),
+			Key::Down | Key::Char('j') | Key::Char('s') => self.move_cursor(0, 1),
+			Key::Left | Key::Char('h') | Key::Char('a') => self.move_cursor(-1, 0),
+			Key::Right | Key::Char('l') | Key::Char('d') => self.move_cursor(1, 0),
+			Key::Char(c @ '1'..='3') => {
+				let idx = c as usize - '1' as usize;
+				if idx < self.game.pieces.len() && !self.game.pieces[idx].used {
+					self.selected = idx;
+				}
+			},
+			Key::Tab => self.select_next(self.selected),
+			Key::Enter | Key::Char(' ') => self.place(),
+			Key::Char('t') => self.turn(),
+			Key::Char('p') => self.hold(self.selected),
+			Key::Char('P') => match self.game.pieces.iter().position(|piece| piece.used) {
+				Some(slot) => self.hold(slot),
+				None => self.message = String::from("The tray is full"),
+			},
+			Key::Char('?') => self.hint(),
+			Key::Char('u') => self.undo(),
+			Key::Char('r') => self.redo(),
+			Key::Char('n') => self.restart(),
+			_ => {},
+		}
+		true
+	}
+
+	fn render(&self) -> String {
+		let game = &self.game;
+		let mut out = String::new();
+
+		let _ = write!(out, "Koala Kombo  Score: {}", game.score);
+		if game.combo() > 1 {
+			let _ = write!(out, "  Combo x{}", game.combo());
+		}
+		let _ = write!(out, "\r\nSeed: {}  Mode: {}", game.seed(), self.mode);
+		if let Some(clock) = game.clock() {
+			let _ = write!(out, "  Time: {}s", clock.seconds_left());
+		}
+		out.push_str("\r\n\r\n");
+
+		// Board, with the selected piece previewed at the cursor
+		let piece = game.pieces[self.selected];
+		let preview = if piece.used {
+			None
+		} else {
+			game.can_place(self.selected, self.cursor)
+		};
+		let preview_color = match game.check_placement(self.selected, self.cursor) {
+			Ok(_) => VALID,
+			Err(_) => INVALID,
+		};
+
+		let border = "─".repeat(self.size.columns * 2);
+		let _ = write!(out, "┌{border}┐\r\n");
+		for row in 0..self.size.rows {
+			out.push('│');
+			for column in 0..self.size.columns {
+				let coord = Coord::new(column, row);
+				let (color, cell) = if preview.as_ref().is_some_and(|cells| cells.contains(&coord)) {
+					(preview_color, "██".to_string())
+				} else {
+					match game.board().cell(coord) {
+						Cell::Empty if coord == self.cursor => (CURSOR, "[]".to_string()),
+						Cell::Empty => (EMPTY, "··".to_string()),
+						Cell::Filled => (FILLED, "██".to_string()),
+						Cell::Stone => (STONE, "▓▓".to_string()),
+						Cell::Ice => (ICE, "▒▒".to_string()),
+						Cell::CrackedIce => (ICE, "░░".to_string()),
+						// The lines left before it opens, capped so the cell stays two columns wide
+						Cell::Locked(clears @ ..=9) => (LOCKED, format!(" {clears}")),
+						Cell::Locked(_) => (LOCKED, " +".to_string()),
+					}
+				};
+				let _ = write!(out, "{color}{cell}{RESET}");
+			}
+			out.push_str("│\r\n");
+		}
+		let _ = write!(out, "└{border}┘\r\n\r\n");
+
+		// Tray, three lines high so every shape fits
+		let mut arts = game.pieces.map(|piece| if piece.used { Vec::new() } else { shape_art(piece.shape) }).to_vec();
+		if game.can_hold() {
+			arts.push(game.held().map(shape_art).unwrap_or_default());
+		}
+		for line in 0..3 {
+			for art in &arts {
+				let text = art.get(line).map_or("", String::as_str);
+				let _ = write!(out, "  {FILLED}{text:<6}{RESET}");
+			}
+			out.push_str("\r\n");
+		}
+		for (i, piece) in game.pieces.iter().enumerate() {
+			let label = match (i == self.selected, piece.used) {
+				(_, true) => String::from("      "),
+				(true, false) => format!("[{}]   ", i + 1),
+				(false, false) => format!(" {}    ", i + 1),
+			};
+			let _ = write!(out, "  {label}");
+		}
+		if game.can_hold() {
+			out.push_str("  hold");
+		}
+
+		let _ = write!(out, "\r\n\r\n{}\r\n\r\n", self.message);
+		out.push_str("arrows/hjkl move  1-3/tab pick  enter place  ");
+		if game.can_rotate() {
+			out.push_str("t turn  ");
+		}
+		if game.can_hold() {
+			out.push_str("p/P hold  ");
+		}
+		out.push_str("? hint  u undo  r redo  n new  q quit\r\n");
+		out
+	}
+}
+
+/// Alternate screen with a hidden cursor, both undone on the way out.
+const ENTER_SCREEN: &str = "\x1b[?1049h\x1b[?25l";
+const LEAVE_SCREEN: &str = "\x1b[?1049l\x1b[?25h";
+
+/// Switches the terminal to the alternate screen and unbuffered input without echo for as long as it lives.
+/// Dropping it restores the terminal, also when the game panics.
+/// Signals are off too, so Ctrl+C comes in as a key that quits.
+/// Reads give up after a second without input, so a running clock keeps being redrawn.
+#[cfg(unix)]
+struct RawMode(Option<libc::termios>);
+
+#[cfg(unix)]
+impl RawMode {
+	/// Piped input isn't a terminal, so it is left as it is and read as it comes.
+	fn enable() -> Self {
+		print!("{ENTER_SCREEN}");
+		// SAFETY: `termios` is plain data and both calls only read or write through the valid pointer
+		unsafe {
+			let mut original = std::mem::zeroed::<libc::termios>();
+			if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+				return Self(None);
+			}
+			let mut raw = original;
+			raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG);
+			raw.c_cc[libc::VMIN] = 0;
+			raw.c_cc[libc::VTIME] = 10;
+			if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) != 0 {
+				return Self(None);
+			}
+			Self(Some(original))
+		}
+	}
+
+	/// If an empty read means the wait timed out rather than that the input ended.
+	fn times_out(&self) -> bool {
+		self.0.is_some()
+	}
+}
+
+#[cfg(unix)]
+impl Drop for RawMode {
+	fn drop(&mut self) {
+		if let Some(original) = &self.0 {
+			// SAFETY: restores the settings read in `enable`
+			unsafe {
+				libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, original);
+			}
+		}
+		print!("{LEAVE_SCREEN}");
+		let _ = io::stdout().flush();
+	}
+}
+
+/// Other platforms keep line-buffered input, so each key is followed by enter.
+#[cfg(not(unix))]
+struct RawMode;
+
+#[cfg(not(unix))]
+impl RawMode {
+	fn enable() -> Self {
+		print!("{ENTER_SCREEN}");
+		Self
+	}
+
+	fn times_out(&self) -> bool {
+		false
+	}
+}
+
+#[cfg(not(unix))]
+impl Drop for RawMode {
+	fn drop(&mut self) {
+		print!("{LEAVE_SCREEN}");
+		let _ = io::stdout().flush();
+	}
+}
+
+/// Parses the value given after `name`, `None` if the option isn't given. Exits on a missing or invalid value.
+fn arg<T: FromStr<Err: fmt::Display>>(name: &str) -> Option<T> {
+	let mut args = std::env::args().skip_while(|arg| arg != name);
+	args.next()?;
+	let Some(value) = args.next() else {
+		eprintln!("tui: {name} needs a value");
+		std::process::exit(1);
+	};
+	match value.parse() {
+		Ok(value) => Some(value),
+		Err(err) => {
+			eprintln!("tui: invalid {name} `{value}`: {err}");
+			std::process::exit(1);
+		},
+	}
+}
+
+fn main() -> io::Result<()> {
+	let mode = arg("--mode").unwrap_or_default();
+	if mode == GameMode::Puzzle {
+		eprintln!("tui: puzzles are only played in the game window");
+		std::process::exit(1);
+	}
+	let mut tui = Tui::new(arg("--seed"), arg("--size").unwrap_or_default(), mode);
+
+	let raw = RawMode::enable();
+	let mut stdout = io::stdout().lock();
+	let mut stdin = io::stdin().lock();
+
+	let mut buf = [0; 16];
+	let mut last_input = Instant::now();
+	'input: loop {
+		write!(stdout, "\x1b[H\x1b[2J{}", tui.render())?;
+		stdout.flush()?;
+
+		let read = stdin.read(&mut buf)?;
+		if read == 0 && !raw.times_out() {
+			break;
+		}
+		tui.tick(last_input.elapsed().as_secs_f32());
+		last_input = Instant::now();
+		for key in parse_keys(&buf[..read]) {
+			if !tui.handle(key) {
+				break 'input;
+			}
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod test {
+	use koala_kombo::koala_kombo::Obstacles;
+
+	use super::*;
+
+	#[test]
+	fn parse_keys_test() {
+		assert_eq!(parse_keys(b"\x1b[A\x1b[Dq"), vec![Key::Up, Key::Left, Key::Char('q')]);
+		assert_eq!(parse_keys(b"\x1b"), vec![Key::Escape]);
+		assert_eq!(parse_keys(b"\x03"), vec![Key::Escape]);
+		assert_eq!(parse_keys(b"\r\t2 "), vec![Key::Enter, Key::Tab, Key::Char('2'), Key::Char(' ')]);
+	}
+
+	#[test]
+	fn shape_art_test() {
+		assert_eq!(shape_art(Shape::OrangeRicky), ["▄▄█"]);
+		assert_eq!(shape_art(Shape::Single), ["▄"]);
+		assert_eq!(shape_art(Shape::TeeweeLeft), [" ▄", "▀█"]);
+		assert_eq!(shape_art(Shape::Huge), ["▄▄▄", "███"]);
+	}
+
+	#[test]
+	fn play_test() {
+		let mut tui = Tui::new(Some(5), BoardSize::default(), GameMode::Classic);
+		for key in parse_keys(b"\x1b[B\x1b[Bll ") {
+			assert!(tui.handle(key));
+		}
+		assert_eq!(tui.game.pieces.iter().filter(|piece| piece.used).count(), 1);
+		assert_ne!(tui.game.board().bits(), 0);
+		assert_ne!(tui.selected, 0);
+
+		assert!(tui.handle(Key::Char('u')));
+		assert!(tui.game.pieces.iter().all(|piece| !piece.used));
+		assert!(!tui.handle(Key::Char('q')));
+	}
+
+	#[test]
+	fn turn_test() {
+		let mut tui = Tui::new(Some(5), BoardSize::default(), GameMode::Twist);
+		tui.selected = tui.game.pieces.iter().position(|piece| piece.shape.rotations().len() > 1).unwrap();
+		let shape = tui.game.pieces[tui.selected].shape;
+		assert!(tui.handle(Key::Char('t')));
+		assert_eq!(tui.game.pieces[tui.selected].shape, shape.rotated());
+		assert!(tui.render().contains("t turn"));
+
+		let mut classic = Tui::new(Some(5), BoardSize::default(), GameMode::Classic);
+		assert!(classic.handle(Key::Char('t')));
+		assert_eq!(classic.message, "This piece can't be turned");
+	}
+
+	#[test]
+	fn hold_test() {
+		let mut tui = Tui::new(Some(5), BoardSize::default(), GameMode::Hold);
+		let shape = tui.game.pieces[0].shape;
+		assert!(tui.handle(Key::Char('p')));
+		assert_eq!(tui.game.held(), Some(shape));
+		assert_eq!(tui.selected, 1);
+		assert!(tui.render().contains("hold"));
+
+		assert!(tui.handle(Key::Char('P')));
+		assert_eq!(tui.game.held(), None);
+		assert_eq!((tui.selected, tui.game.pieces[0].shape), (0, shape));
+		assert!(tui.handle(Key::Char('P')));
+		assert_eq!(tui.message, "The tray is full");
+	}
+
+	#[test]
+	fn blitz_test() {
+		let mut tui = Tui::new(Some(5), BoardSize::default(), GameMode::Blitz);
+		assert!(tui.render().contains("Time: 90s"));
+		tui.tick(100.0);
+		assert!(tui.game.is_game_over());
+		assert!(tui.message.starts_with("Game over!"));
+		assert!(tui.handle(Key::Enter));
+		assert_eq!(tui.game.board().bits(), 0);
+	}
+
+	#[test]
+	fn daily_test() {
+		let mut tui = Tui::new(None, BoardSize::new(6, 5), GameMode::Daily);
+		assert_eq!(tui.game.size(), BoardSize::default());
+		tui.move_cursor(20, 20);
+		let size = BoardSize::default();
+		assert_eq!(tui.cursor, Coord::new(size.columns - 1, size.rows - 1));
+	}
+
+	#[test]
+	fn obstacles_test() {
+		let mut tui = Tui::new(Some(5), BoardSize::default(), GameMode::Obstacles);
+		let render = tui.render();
+		assert!(render.contains("▓▓") && render.contains("▒▒") && render.contains(" 5"));
+
+		// Long locks still take two columns
+		let obstacles = Obstacles {
+			locked: 1,
+			lock_clears: 120,
+			..Default::default()
+		};
+		tui.game = KoalaKombo::builder().with_seed(5).with_obstacles(obstacles).build();
+		let render = tui.render();
+		assert!(render.contains(" +") && !render.contains("120"));
+	}
+}
@@ -0,0 +1,199 @@
+//! Re-simulates replays to check the scores they claim.
+//!
+//! `verify <replay file>...`, with `-` reading a replay from stdin.
+//!
+//! Every move is played against the engine rules, so a replay only passes if the game could really have been played.
+//! Prints the recomputed score of each replay, or the first move the rules refuse, counted from 0 like its pieces.
+//! Games played against a clock can't be checked, as replays don't record when moves were made.
+//! Exits with a failure if any replay was rejected or couldn't be checked.
+
+use std::{
+	fmt,
+	io::{self, Read},
+	process::ExitCode,
+};
+
+use koala_kombo::{
+	koala_kombo::{Move, PlacementError},
+	replay::Replay,
+};
+
+/// What a replay turned out to be.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Verdict {
+	/// Every move is legal and earns this score.
+	Valid { score: u32, moves: usize, game_over: bool },
+	/// The move at `index` can't be played.
+	Illegal {
+		index: usize,
+		mv: Move,
+		err: PlacementError,
+	},
+	/// Not a replay of the rules this build plays.
+	Invalid(String),
+	/// Every move is legal, but the replay lacks what it takes to check the rest of the rules.
+	Unverifiable(String),
+}
+
+impl Verdict {
+	fn is_valid(&self) -> bool {
+		matches!(self, Verdict::Valid { .. })
+	}
+}
+
+impl fmt::Display for Verdict {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Verdict::Valid {
+				score,
+				moves,
+				game_over,
+			} => {
+				let end = if *game_over { "game over" } else { "unfinished" };
+				write!(f, "valid, score {score} in {moves} moves, {end}")
+			},
+			Verdict::Illegal { index, mv, err } if mv.hold => {
+				write!(f, "illegal move {index} (holding piece {}): {err}", mv.piece_idx)
+			},
+			Verdict::Illegal { index, mv, err } => write!(
+				f,
+				"illegal move {index} (piece {} at column {}, row {}): {err}",
+				mv.piece_idx, mv.coord.column, mv.coord.row
+			),
+			Verdict::Invalid(err) => write!(f, "invalid replay: {err}"),
+			Verdict::Unverifiable(reason) => write!(f, "unverifiable: {reason}"),
+		}
+	}
+}
+
+fn verify(text: &str) -> Verdict {
+	let replay = match text.parse::<Replay>() {
+		Ok(replay) => replay,
+		Err(err) => return Verdict::Invalid(err),
+	};
+	match replay.play() {
+		Ok(game) if game.clock().is_some() => Verdict::Unverifiable(String::from(
+			"the replay doesn't record when moves were made, so the clock can't be checked",
+		)),
+		Ok(game) => Verdict::Valid {
+			score: game.score,
+			moves: replay.moves.len(),
+			game_over: game.is_game_over(),
+		},
+		Err((index, err)) => Verdict::Illegal {
+			index,
+			mv: replay.moves[index],
+			err,
+		},
+	}
+}
+
+fn read(path: &str) -> io::Result<String> {
+	if path == "-" {
+		let mut text = String::new();
+		io::stdin().read_to_string(&mut text)?;
+		Ok(text)
+	} else {
+		std::fs::read_to_string(path)
+	}
+}
+
+fn main() -> ExitCode {
+	let paths = std::env::args().skip(1).collect::<Vec<_>>();
+	if paths.is_empty() {
+		eprintln!("usage: verify <replay file>...");
+		return ExitCode::FAILURE;
+	}
+
+	let mut all_valid = true;
+	for path in paths {
+		match read(&path) {
+			Ok(text) => {
+				let verdict = verify(&text);
+				all_valid &= verdict.is_valid();
+				println!("{path}: {verdict}");
+			},
+			Err(err) => {
+				all_valid = false;
+				println!("{path}: can't be read: {err}");
+			},
+		}
+	}
+
+	if all_valid {
+		ExitCode::SUCCESS
+	} else {
+		ExitCode::FAILURE
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use koala_kombo::{
+		koala_kombo::{BoardSize, Coord},
+		mode::GameMode,
+		solver::Solver,
+	};
+
+	#[test]
+	fn verify_test() {
+		let mut game = GameMode::Classic.builder().with_size(BoardSize::square(6)).with_seed(5).build();
+		while Solver::default().play(&mut game) > 0 {}
+		let mut replay = Replay::from_game(GameMode::Classic, &game);
+
+		let verdict = verify(&replay.to_string());
+		assert_eq!(
+			verdict,
+			Verdict::Valid {
+				score: game.score,
+				moves: game.moves().len(),
+				game_over: true,
+			}
+		);
+
+		// Moving a piece onto one placed before must be refused
+		let mut first = replay.game();
+		first.place_shape(replay.moves[0].piece_idx, replay.moves[0].coord).unwrap();
+		let piece_idx = replay.moves[1].piece_idx;
+		let coord = first
+			.size()
+			.coords()
+			.find(|&coord| matches!(first.check_placement(piece_idx, coord), Err(PlacementError::Overlaps(_))));
+		replay.moves[1].coord = coord.unwrap();
+		assert!(matches!(
+			verify(&replay.to_string()),
+			Verdict::Illegal {
+				index: 1,
+				err: PlacementError::Overlaps(_),
+				..
+			}
+		));
+
+		replay.moves[1].coord = Coord::new(usize::MAX, 0);
+		assert!(matches!(
+			verify(&replay.to_string()),
+			Verdict::Illegal {
+				err: PlacementError::OutOfBounds,
+				..
+			}
+		));
+
+		assert!(matches!(verify("score 1000000"), Verdict::Invalid(_)));
+
+		let hold = Verdict::Illegal {
+			index: 2,
+			mv: Move::hold(1),
+			err: PlacementError::CannotHold,
+		};
+		assert_eq!(hold.to_string(), "illegal move 2 (holding piece 1): piece can't be held");
+	}
+
+	#[test]
+	fn blitz_test() {
+		let mut game = GameMode::Blitz.builder().with_seed(5).build();
+		Solver::default().play(&mut game);
+		let replay = Replay::from_game(GameMode::Blitz, &game);
+		assert!(matches!(verify(&replay.to_string()), Verdict::Unverifiable(_)));
+	}
+}
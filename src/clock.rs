@@ -0,0 +1,105 @@
+//! Countdowns for timed play.
+//!
+//! A clock never reads the system time, it only runs down by the time it is ticked with.
+//! The window ticks it every frame, while tests tick it by hand.
+
+/// Seconds left in a timed game, topped up with a bonus for every cleared line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Clock {
+	remaining: f32,
+	line_bonus: f32,
+	paused: bool,
+}
+
+impl Clock {
+	/// A running clock with `seconds` on it and no bonus.
+	pub fn new(seconds: f32) -> Self {
+		Self {
+			remaining: seconds.max(0.0),
+			line_bonus: 0.0,
+			paused: false,
+		}
+	}
+
+	/// Gives `seconds` back for every line cleared.
+	pub fn with_line_bonus(mut self, seconds: f32) -> Self {
+		self.line_bonus = seconds;
+		self
+	}
+
+	pub fn remaining(&self) -> f32 {
+		self.remaining
+	}
+
+	/// Whole seconds left, as shown to the player: a clock only reads zero once it ran out.
+	pub fn seconds_left(&self) -> u32 {
+		self.remaining.ceil() as u32
+	}
+
+	pub fn line_bonus(&self) -> f32 {
+		self.line_bonus
+	}
+
+	pub fn is_expired(&self) -> bool {
+		self.remaining <= 0.0
+	}
+
+	pub fn is_paused(&self) -> bool {
+		self.paused
+	}
+
+	pub fn pause(&mut self) {
+		self.paused = true;
+	}
+
+	pub fn resume(&mut self) {
+		self.paused = false;
+	}
+
+	/// Runs the clock down by `dt` seconds unless it is paused.
+	/// Returns if it ran out with this tick.
+	pub fn tick(&mut self, dt: f32) -> bool {
+		if self.paused || self.is_expired() {
+			return false;
+		}
+		self.remaining = (self.remaining - dt).max(0.0);
+		self.is_expired()
+	}
+
+	/// Adds the bonus for clearing `lines`. A clock that ran out stays out.
+	pub fn reward_lines(&mut self, lines: usize) {
+		if !self.is_expired() {
+			self.remaining += self.line_bonus * lines as f32;
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn clock_test() {
+		let mut clock = Clock::new(10.0).with_line_bonus(2.0);
+		assert!(!clock.tick(4.5));
+		assert_eq!(clock.seconds_left(), 6);
+
+		clock.pause();
+		assert!(!clock.tick(100.0));
+		assert_eq!(clock.remaining(), 5.5);
+		clock.resume();
+
+		clock.reward_lines(2);
+		assert_eq!(clock.remaining(), 9.5);
+		assert!(!clock.tick(9.0));
+		assert_eq!(clock.seconds_left(), 1);
+		assert!(clock.tick(1.0));
+		assert!(clock.is_expired());
+		assert_eq!(clock.seconds_left(), 0);
+
+		// Running out only happens once, and clearing lines afterwards doesn't bring it back
+		assert!(!clock.tick(1.0));
+		clock.reward_lines(3);
+		assert!(clock.is_expired());
+	}
+}
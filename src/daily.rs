@@ -0,0 +1,161 @@
+//! The daily challenge: one game a day, dealt alike for everyone who plays it that day.
+//!
+//! Only the first game of a day is scored. Its score goes into a local history with every move,
+//! so leaving a bad game half way doesn't earn another try.
+
+use std::{fmt, io, str::FromStr};
+
+use crate::{
+	date::Date,
+	koala_kombo::{BoardSize, Coord, KoalaKombo},
+	mode::GameMode,
+	storage,
+};
+
+/// Storage key of the history of daily results.
+const KEY: &str = "daily";
+
+/// Returns the seed of the challenge of the date, its digits spelling the date, e.g. `20241231`.
+pub fn seed(date: Date) -> u64 {
+	u64::from(date.year.unsigned_abs()) * 10_000 + u64::from(date.month) * 100 + u64::from(date.day)
+}
+
+/// Builds the challenge of the date, on the default board so everyone gets the same game.
+pub fn game(date: Date) -> KoalaKombo {
+	GameMode::Daily.builder().with_size(BoardSize::default()).with_seed(seed(date)).build()
+}
+
+/// Returns a short text to share the result of the game: the date, the score and the final board in emoji.
+pub fn share(date: Date, game: &KoalaKombo) -> String {
+	let mut text = format!("Koala Kombo daily {date}\nScore: {}\n", game.score);
+	let size = game.size();
+	for row in 0..size.rows {
+		text.extend((0..size.columns).map(|column| {
+			if game.board().filled(Coord::new(column, row)) {
+				'🟦'
+			} else {
+				'⬛'
+			}
+		}));
+		text.push('\n');
+	}
+	text
+}
+
+/// The scored game of a day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DailyResult {
+	pub date: Date,
+	pub score: u32,
+}
+
+impl fmt::Display for DailyResult {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} {}", self.date, self.score)
+	}
+}
+
+/// Parses the `<date> <score>` line written by [`Display`](fmt::Display).
+impl FromStr for DailyResult {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (date, score) = s.trim().split_once(' ').ok_or_else(|| format!("invalid daily result `{s}`"))?;
+		Ok(Self {
+			date: date.parse()?,
+			score: score.parse().map_err(|_| format!("invalid score `{score}`"))?,
+		})
+	}
+}
+
+/// Results of the daily challenges played, oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DailyHistory {
+	results: Vec<DailyResult>,
+}
+
+impl DailyHistory {
+	/// Loads the stored history. Unreadable lines are skipped.
+	pub fn load() -> Self {
+		let mut history = Self::default();
+		for result in storage::load(KEY).unwrap_or_default().lines().filter_map(|line| line.parse().ok()) {
+			history.record(result);
+		}
+		history
+	}
+
+	pub fn save(&self) -> io::Result<()> {
+		storage::save(KEY, &self.to_string())
+	}
+
+	pub fn results(&self) -> &[DailyResult] {
+		&self.results
+	}
+
+	/// Returns the result of the day, `None` if its challenge wasn't played yet.
+	pub fn result(&self, date: Date) -> Option<&DailyResult> {
+		self.results.iter().find(|result| result.date == date)
+	}
+
+	/// Records the score of the day's game, replacing the score recorded for that day so far.
+	pub fn record(&mut self, result: DailyResult) {
+		match self.results.binary_search_by_key(&result.date, |result| result.date) {
+			Ok(idx) => self.results[idx] = result,
+			Err(idx) => self.results.insert(idx, result),
+		}
+	}
+}
+
+/// One result per line, in the format of [`DailyResult`]'s `Display`.
+impl fmt::Display for DailyHistory {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		self.results.iter().try_for_each(|result| writeln!(f, "{result}"))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::koala_kombo::{Piece, Shape};
+
+	#[test]
+	fn seed_test() {
+		let date = Date::new(2024, 12, 31);
+		assert_eq!(seed(date), 20_241_231);
+		assert_eq!(game(date).pieces.map(|p| p.shape), game(date).pieces.map(|p| p.shape));
+		assert_ne!(seed(date), seed(Date::new(2025, 1, 1)));
+	}
+
+	#[test]
+	fn history_test() {
+		let mut history = DailyHistory::default();
+		let (first, second) = (Date::new(2024, 5, 2), Date::new(2024, 5, 1));
+		history.record(DailyResult { date: first, score: 10 });
+		history.record(DailyResult {
+			date: second,
+			score: 30,
+		});
+		history.record(DailyResult { date: first, score: 40 });
+		assert_eq!(history.result(first).map(|result| result.score), Some(40));
+		assert_eq!(history.result(Date::new(2024, 5, 3)), None);
+
+		let text = history.to_string();
+		assert_eq!(text, "2024-05-01 30\n2024-05-02 40\n");
+		let parsed = text.lines().map(|line| line.parse().unwrap()).collect::<Vec<DailyResult>>();
+		assert_eq!(parsed, history.results());
+		assert!("2024-05-01".parse::<DailyResult>().is_err());
+	}
+
+	#[test]
+	fn share_test() {
+		let mut game = KoalaKombo::builder().with_size(BoardSize::new(3, 2)).build();
+		game.pieces[0] = Piece {
+			shape: Shape::Single,
+			used: false,
+		};
+		assert!(game.place_shape(0, Coord::new(1, 0)).is_ok());
+
+		let text = share(Date::new(2024, 5, 1), &game);
+		assert_eq!(text, format!("Koala Kombo daily 2024-05-01\nScore: {}\n⬛🟦⬛\n⬛⬛⬛\n", game.score));
+	}
+}
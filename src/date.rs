@@ -0,0 +1,111 @@
+use std::{fmt, str::FromStr};
+
+/// A calendar day in UTC, shown and parsed as `YYYY-MM-DD`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+	pub year: i32,
+	pub month: u32,
+	pub day: u32,
+}
+
+impl Date {
+	pub fn new(year: i32, month: u32, day: u32) -> Self {
+		Self { year, month, day }
+	}
+
+	/// Returns the current day in UTC.
+	pub fn today() -> Self {
+		Self::from_days(unix_millis().div_euclid(86_400_000))
+	}
+
+	/// Converts the number of days since 1970-01-01 to a date.
+	pub fn from_days(days: i64) -> Self {
+		// Howard Hinnant's `civil_from_days`
+		let z = days + 719_468;
+		let era = z.div_euclid(146_097);
+		let doe = z.rem_euclid(146_097);
+		let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+		let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+		let mp = (5 * doy + 2) / 153;
+		let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+		let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+		let year = (yoe + era * 400 + i64::from(month <= 2)) as i32;
+		Self { year, month, day }
+	}
+
+	/// Returns the number of days since 1970-01-01.
+	pub fn days(self) -> i64 {
+		// Howard Hinnant's `days_from_civil`
+		let year = i64::from(self.year) - i64::from(self.month <= 2);
+		let era = year.div_euclid(400);
+		let yoe = year.rem_euclid(400);
+		let month = i64::from(self.month);
+		let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(self.day) - 1;
+		let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+		era * 146_097 + doe - 719_468
+	}
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn unix_millis() -> i64 {
+	let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default();
+	now.as_millis() as i64
+}
+
+/// `SystemTime` is not available in the browser, so the time comes from JavaScript.
+#[cfg(target_arch = "wasm32")]
+fn unix_millis() -> i64 {
+	js_sys::Date::now() as i64
+}
+
+impl fmt::Display for Date {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+	}
+}
+
+impl FromStr for Date {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let invalid = || format!("invalid date `{s}`");
+		let mut parts = s.trim().splitn(3, '-');
+		let mut next = || parts.next().ok_or_else(invalid);
+		let (year, month, day) = (next()?, next()?, next()?);
+
+		let date = Self::new(
+			year.parse().map_err(|_| invalid())?,
+			month.parse().map_err(|_| invalid())?,
+			day.parse().map_err(|_| invalid())?,
+		);
+		// Days that don't exist, like the 31st of April, don't survive the round trip
+		if Self::from_days(date.days()) != date {
+			return Err(invalid());
+		}
+		Ok(date)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn days_test() {
+		assert_eq!(Date::from_days(0), Date::new(1970, 1, 1));
+		assert_eq!(Date::from_days(19_782), Date::new(2024, 2, 29));
+		assert_eq!(Date::new(2000, 3, 1).days(), 11_017);
+		for days in -1_000..1_000 {
+			assert_eq!(Date::from_days(days * 37).days(), days * 37);
+		}
+	}
+
+	#[test]
+	fn parse_test() {
+		assert_eq!("2024-02-29".parse(), Ok(Date::new(2024, 2, 29)));
+		assert_eq!(Date::new(812, 5, 3).to_string(), "0812-05-03");
+		assert!("2023-02-29".parse::<Date>().is_err());
+		assert!("2023-13-01".parse::<Date>().is_err());
+		assert!("yesterday".parse::<Date>().is_err());
+	}
+}
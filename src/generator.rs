@@ -0,0 +1,262 @@
+use std::fmt::Debug;
+
+#[cfg(feature = "gui")]
+use fyrox::core::visitor::prelude::*;
+use rand::{
+	RngExt,
+	seq::{IndexedRandom, SliceRandom},
+};
+
+use crate::{
+	koala_kombo::{Board, Shape},
+	rng::GameRng,
+};
+
+/// Decides which shapes are dealt into the tray.
+/// Every random decision must come from the passed `rng` so seeded games stay reproducible.
+pub trait PieceGenerator: Debug + Send + Sync {
+	/// Picks the shape for the next tray slot. `board` is the board the tray is dealt onto.
+	fn next_shape(&mut self, rng: &mut GameRng, board: &Board) -> Shape;
+
+	/// Clones the generator together with its internal state.
+	fn clone_box(&self) -> Box<dyn PieceGenerator>;
+
+	/// Returns if there are shapes left to deal. Slots dealt once this is `false` stay empty.
+	fn has_next(&self) -> bool {
+		true
+	}
+
+	/// Saves or loads the internal state that decides the upcoming shapes, so a saved game deals on the same.
+	/// Stateless generators have nothing to store.
+	#[cfg(feature = "gui")]
+	fn visit_state(&mut self, _name: &str, _visitor: &mut Visitor) -> VisitResult {
+		Ok(())
+	}
+}
+
+impl Clone for Box<dyn PieceGenerator> {
+	fn clone(&self) -> Self {
+		self.clone_box()
+	}
+}
+
+/// Every shape is equally likely for every slot.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Uniform;
+
+impl PieceGenerator for Uniform {
+	fn next_shape(&mut self, rng: &mut GameRng, _board: &Board) -> Shape {
+		Shape::DEALT[rng.random_range(0..Shape::DEALT.len())]
+	}
+
+	fn clone_box(&self) -> Box<dyn PieceGenerator> {
+		Box::new(*self)
+	}
+}
+
+/// Each shape is picked with a probability proportional to its weight.
+/// Shapes missing from the list are never dealt.
+#[derive(Debug, Clone)]
+pub struct Weighted {
+	weights: Vec<(Shape, u32)>,
+}
+
+impl Weighted {
+	/// # Panics
+	/// If no shape has a weight above zero.
+	pub fn new(weights: impl IntoIterator<Item = (Shape, u32)>) -> Self {
+		let weights = weights.into_iter().collect::<Vec<_>>();
+		assert!(weights.iter().any(|&(_, weight)| weight > 0), "at least one shape needs a positive weight");
+		Self { weights }
+	}
+
+	/// Favors small shapes: a single is dealt nine times as often as the 3x3 square.
+	pub fn by_size() -> Self {
+		Self::new(Shape::DEALT.iter().map(|&shape| (shape, 10 - shape.get_coords().len() as u32)))
+	}
+}
+
+impl PieceGenerator for Weighted {
+	fn next_shape(&mut self, rng: &mut GameRng, _board: &Board) -> Shape {
+		self.weights.choose_weighted(rng, |&(_, weight)| weight).map(|&(shape, _)| shape).unwrap()
+	}
+
+	fn clone_box(&self) -> Box<dyn PieceGenerator> {
+		Box::new(self.clone())
+	}
+}
+
+/// Deals from a shuffled bag holding every shape `copies` times and refills it once empty,
+/// so no shape is left out for long and none can come up many times in a row.
+#[derive(Debug, Clone)]
+pub struct Bag {
+	copies: usize,
+	bag: Vec<Shape>,
+}
+
+impl Default for Bag {
+	fn default() -> Self {
+		Self::new(1)
+	}
+}
+
+impl Bag {
+	pub fn new(copies: usize) -> Self {
+		Self {
+			copies: copies.max(1),
+			bag: Vec::new(),
+		}
+	}
+}
+
+impl PieceGenerator for Bag {
+	fn next_shape(&mut self, rng: &mut GameRng, _board: &Board) -> Shape {
+		if self.bag.is_empty() {
+			for _ in 0..self.copies {
+				self.bag.extend_from_slice(Shape::DEALT);
+			}
+			self.bag.shuffle(rng);
+		}
+		self.bag.pop().unwrap()
+	}
+
+	fn clone_box(&self) -> Box<dyn PieceGenerator> {
+		Box::new(self.clone())
+	}
+
+	#[cfg(feature = "gui")]
+	fn visit_state(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+		let mut region = visitor.enter_region(name)?;
+		let mut len = self.bag.len() as u32;
+		len.visit("Len", &mut region)?;
+		if region.is_reading() {
+			self.bag = vec![Shape::Single; len as usize];
+		}
+		for (i, shape) in self.bag.iter_mut().enumerate() {
+			shape.visit(&format!("Shape{i}"), &mut region)?;
+		}
+		Ok(())
+	}
+}
+
+/// Deals a fixed run of shapes in order, then nothing. Puzzles use it to hand out the same pieces every time.
+#[derive(Debug, Clone)]
+pub struct Sequence {
+	/// Shapes still to deal, the next one last.
+	remaining: Vec<Shape>,
+}
+
+impl Sequence {
+	pub fn new(shapes: impl IntoIterator<Item = Shape>) -> Self {
+		let mut remaining = shapes.into_iter().collect::<Vec<_>>();
+		remaining.reverse();
+		Self { remaining }
+	}
+}
+
+impl PieceGenerator for Sequence {
+	/// # Panics
+	/// If the sequence is used up, check [`PieceGenerator::has_next`] first.
+	fn next_shape(&mut self, _rng: &mut GameRng, _board: &Board) -> Shape {
+		self.remaining.pop().expect("the sequence has no shapes left")
+	}
+
+	fn clone_box(&self) -> Box<dyn PieceGenerator> {
+		Box::new(self.clone())
+	}
+
+	fn has_next(&self) -> bool {
+		!self.remaining.is_empty()
+	}
+
+	#[cfg(feature = "gui")]
+	fn visit_state(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+		let mut region = visitor.enter_region(name)?;
+		let mut len = self.remaining.len() as u32;
+		len.visit("Len", &mut region)?;
+		if region.is_reading() {
+			self.remaining = vec![Shape::Single; len as usize];
+		}
+		for (i, shape) in self.remaining.iter_mut().enumerate() {
+			shape.visit(&format!("Shape{i}"), &mut region)?;
+		}
+		Ok(())
+	}
+}
+
+/// Only deals shapes that still fit somewhere on the board, so a crowded board doesn't get a dead tray.
+/// Falls back to [`Uniform`] once nothing fits anymore.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Adaptive;
+
+impl PieceGenerator for Adaptive {
+	fn next_shape(&mut self, rng: &mut GameRng, board: &Board) -> Shape {
+		let fitting = Shape::DEALT.iter().copied().filter(|&shape| board.fits_anywhere(shape)).collect::<Vec<_>>();
+		match fitting.choose(rng) {
+			Some(&shape) => shape,
+			None => Uniform.next_shape(rng, board),
+		}
+	}
+
+	fn clone_box(&self) -> Box<dyn PieceGenerator> {
+		Box::new(*self)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::koala_kombo::BoardSize;
+
+	#[test]
+	fn weighted_test() {
+		let mut rng = GameRng::new(7);
+		let board = Board::default();
+		let mut generator = Weighted::new([(Shape::Hero, 0), (Shape::Single, 3), (Shape::Duce, 1)]);
+		for _ in 0..100 {
+			assert_ne!(generator.next_shape(&mut rng, &board), Shape::Hero);
+		}
+	}
+
+	#[test]
+	fn bag_test() {
+		let mut rng = GameRng::new(7);
+		let board = Board::default();
+		let mut generator = Bag::new(2);
+
+		// Every round of the bag deals each shape exactly `copies` times
+		for _ in 0..3 {
+			let round = (0..Shape::DEALT.len() * 2).map(|_| generator.next_shape(&mut rng, &board)).collect::<Vec<_>>();
+			for shape in Shape::DEALT {
+				assert_eq!(round.iter().filter(|&dealt| dealt == shape).count(), 2);
+			}
+		}
+	}
+
+	#[test]
+	fn sequence_test() {
+		let mut rng = GameRng::new(7);
+		let board = Board::default();
+		let mut generator = Sequence::new([Shape::Hero, Shape::Single]);
+		assert_eq!(generator.next_shape(&mut rng, &board), Shape::Hero);
+		assert!(generator.has_next());
+		assert_eq!(generator.next_shape(&mut rng, &board), Shape::Single);
+		assert!(!generator.has_next());
+	}
+
+	#[test]
+	fn adaptive_test() {
+		let mut rng = GameRng::new(7);
+		let mut board = Board::new(BoardSize::square(4));
+
+		// Leave a single hole so only `Single` fits
+		for coord in board.size().coords().skip(1) {
+			board.set(coord, true);
+		}
+
+		let mut generator = Adaptive;
+		for _ in 0..20 {
+			assert_eq!(generator.next_shape(&mut rng, &board), Shape::Single);
+		}
+	}
+}
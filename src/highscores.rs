@@ -0,0 +1,170 @@
+use std::{fmt, io, str::FromStr};
+
+use crate::{date::Date, mode::GameMode, storage};
+
+/// Storage key of the high-score table.
+const KEY: &str = "highscores";
+
+/// A finished game that made it onto the table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HighScore {
+	pub mode: GameMode,
+	pub date: Date,
+	pub score: u32,
+	pub seed: u64,
+}
+
+impl fmt::Display for HighScore {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} {} {} {}", self.mode, self.date, self.score, self.seed)
+	}
+}
+
+/// Parses the `<mode> <date> <score> <seed>` line written by [`Display`](fmt::Display).
+impl FromStr for HighScore {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let fields = s.split_whitespace().collect::<Vec<_>>();
+		let &[mode, date, score, seed] = fields.as_slice() else {
+			return Err(format!("invalid high score `{s}`"));
+		};
+		Ok(Self {
+			mode: mode.parse()?,
+			date: date.parse()?,
+			score: score.parse().map_err(|_| format!("invalid score `{score}`"))?,
+			seed: seed.parse().map_err(|_| format!("invalid seed `{seed}`"))?,
+		})
+	}
+}
+
+/// The best results per game mode, best first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HighScores {
+	limit: usize,
+	entries: Vec<HighScore>,
+}
+
+impl Default for HighScores {
+	fn default() -> Self {
+		Self::new(Self::DEFAULT_LIMIT)
+	}
+}
+
+impl HighScores {
+	/// Number of results kept per mode unless configured otherwise.
+	pub const DEFAULT_LIMIT: usize = 10;
+
+	/// Creates an empty table keeping the best `limit` results of every mode.
+	pub fn new(limit: usize) -> Self {
+		Self {
+			limit: limit.max(1),
+			entries: Vec::new(),
+		}
+	}
+
+	/// Loads the stored table. Unreadable lines are skipped, so a damaged file loses as little as possible.
+	pub fn load() -> Self {
+		let mut scores = Self::default();
+		for entry in storage::load(KEY).unwrap_or_default().lines().filter_map(|line| line.parse().ok()) {
+			scores.record(entry);
+		}
+		scores
+	}
+
+	pub fn save(&self) -> io::Result<()> {
+		storage::save(KEY, &self.to_string())
+	}
+
+	/// Results of the mode, best first.
+	pub fn top(&self, mode: GameMode) -> impl Iterator<Item = &HighScore> {
+		self.entries.iter().filter(move |entry| entry.mode == mode)
+	}
+
+	/// Best score of the mode, `None` before its first recorded game.
+	pub fn best(&self, mode: GameMode) -> Option<u32> {
+		self.top(mode).next().map(|entry| entry.score)
+	}
+
+	/// Records a finished game and returns its rank within its mode, `0` being a new high score.
+	/// Returns `None` if it didn't make the table. Ties rank below the earlier result.
+	pub fn record(&mut self, entry: HighScore) -> Option<usize> {
+		let rank = self.top(entry.mode).take_while(|other| other.score >= entry.score).count();
+		if rank >= self.limit {
+			return None;
+		}
+
+		let idx = self.entries.iter().position(|other| other.mode == entry.mode && other.score < entry.score);
+		let mode = entry.mode;
+		self.entries.insert(idx.unwrap_or(self.entries.len()), entry);
+
+		// Drop whatever fell off the end of the mode's table
+		if let Some(last) = self.entries.iter().enumerate().filter(|(_, other)| other.mode == mode).nth(self.limit) {
+			self.entries.remove(last.0);
+		}
+		Some(rank)
+	}
+
+	/// Records a game again that was recorded as `old` before, e.g. when it ended once more after undoing past its end.
+	/// Returns its new rank like [`Self::record`].
+	pub fn replace(&mut self, old: &HighScore, entry: HighScore) -> Option<usize> {
+		if let Some(idx) = self.entries.iter().position(|other| other == old) {
+			self.entries.remove(idx);
+		}
+		self.record(entry)
+	}
+}
+
+/// One result per line, in the format of [`HighScore`]'s `Display`.
+impl fmt::Display for HighScores {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		self.entries.iter().try_for_each(|entry| writeln!(f, "{entry}"))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn entry(score: u32) -> HighScore {
+		HighScore {
+			mode: GameMode::Classic,
+			date: Date::new(2024, 5, 1),
+			score,
+			seed: score as u64,
+		}
+	}
+
+	#[test]
+	fn record_test() {
+		let mut scores = HighScores::new(3);
+		assert_eq!(scores.best(GameMode::Classic), None);
+		assert_eq!(scores.record(entry(50)), Some(0));
+		assert_eq!(scores.record(entry(80)), Some(0));
+		assert_eq!(scores.record(entry(50)), Some(2));
+		assert_eq!(scores.record(entry(10)), None);
+		assert_eq!(scores.record(entry(60)), Some(1));
+
+		assert_eq!(scores.top(GameMode::Classic).map(|e| e.score).collect::<Vec<_>>(), [80, 60, 50]);
+		assert_eq!(scores.best(GameMode::Classic), Some(80));
+
+		// A game that ends again takes the place of its earlier result
+		assert_eq!(scores.replace(&entry(60), entry(90)), Some(0));
+		assert_eq!(scores.top(GameMode::Classic).map(|e| e.score).collect::<Vec<_>>(), [90, 80, 50]);
+		assert_eq!(scores.replace(&entry(10), entry(70)), Some(2));
+		assert_eq!(scores.top(GameMode::Classic).map(|e| e.score).collect::<Vec<_>>(), [90, 80, 70]);
+	}
+
+	#[test]
+	fn parse_test() {
+		let mut scores = HighScores::default();
+		scores.record(entry(120));
+		scores.record(entry(7));
+
+		let text = scores.to_string();
+		assert_eq!(text, "classic 2024-05-01 120 120\nclassic 2024-05-01 7 7\n");
+		let parsed = text.lines().map(|line| line.parse().unwrap()).collect::<Vec<HighScore>>();
+		assert_eq!(parsed, scores.top(GameMode::Classic).cloned().collect::<Vec<_>>());
+		assert!("classic 2024-05-01 lots 7".parse::<HighScore>().is_err());
+	}
+}
@@ -0,0 +1,248 @@
+use std::fmt;
+
+use crate::koala_kombo::{Board, Coord, KoalaKombo};
+
+/// A suggested placement for the current tray, with the reasons it was picked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hint {
+	pub piece_idx: usize,
+	pub coord: Coord,
+	/// Clockwise turns to give the piece before placing it, see [`KoalaKombo::rotate_piece`].
+	pub turns: u8,
+	/// Whether the held piece is swapped into slot `piece_idx` first and placed from there,
+	/// see [`KoalaKombo::hold_piece`]. `turns` then turns the held piece.
+	pub hold: bool,
+	/// The board cells the piece would cover.
+	pub cells: Vec<Coord>,
+	/// Rows and columns the placement would clear.
+	pub lines_cleared: usize,
+	/// Number of other unused tray pieces that still fit somewhere afterwards.
+	pub pieces_fitting_after: usize,
+	/// Number of other unused tray pieces.
+	pub other_pieces: usize,
+}
+
+impl Hint {
+	/// Returns if every other unused tray piece still fits somewhere after this placement.
+	pub fn keeps_room(&self) -> bool {
+		self.pieces_fitting_after == self.other_pieces
+	}
+}
+
+impl fmt::Display for Hint {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let piece = self.piece_idx + 1;
+		let turns = match self.turns {
+			1 => String::from("once"),
+			2 => String::from("twice"),
+			turns => format!("{turns} times"),
+		};
+		match (self.hold, self.turns) {
+			(false, 0) => write!(f, "Place piece {piece}")?,
+			(false, _) => write!(f, "Turn piece {piece} {turns} and place it")?,
+			(true, 0) => write!(f, "Swap the held piece into slot {piece} and place it")?,
+			(true, _) => write!(f, "Swap the held piece into slot {piece}, turn it {turns} and place it")?,
+		}
+		write!(f, " at column {}, row {}", self.coord.column + 1, self.coord.row + 1)?;
+		match self.lines_cleared {
+			0 => write!(f, ", clearing nothing")?,
+			1 => write!(f, ", clearing 1 line")?,
+			lines => write!(f, ", clearing {lines} lines")?,
+		}
+		if self.other_pieces == 0 {
+			write!(f, ".")
+		} else if self.keeps_room() {
+			write!(f, ", and keep room for the other pieces.")
+		} else {
+			write!(f, ", but only {} of the other {} pieces will still fit.", self.pieces_fitting_after, self.other_pieces)
+		}
+	}
+}
+
+impl KoalaKombo {
+	/// Suggests a placement for one of the unused tray pieces, `None` if nothing fits.
+	/// Pieces are tried in every orientation the rules allow, see [`KoalaKombo::orientations`],
+	/// and a held piece is tried in every slot it can be swapped into.
+	///
+	/// Placements that keep room for the rest of the tray win first, then those clearing more lines,
+	/// then those that nestle against filled cells and walls instead of splitting up open space.
+	pub fn hint(&self) -> Option<Hint> {
+		let mut best = None;
+		for piece_idx in 0..self.pieces.len() {
+			self.rank_placements(piece_idx, false, &mut best);
+		}
+		if self.held().is_some() {
+			for slot in 0..self.pieces.len() {
+				let mut swapped = self.clone();
+				if swapped.hold_piece(slot) {
+					swapped.rank_placements(slot, true, &mut best);
+				}
+			}
+		}
+
+		best.map(|(_, hint)| hint)
+	}
+
+	/// Ranks every placement of the piece into `best`, which only changes for a better one.
+	fn rank_placements(&self, piece_idx: usize, hold: bool, best: &mut Option<(HintRank, Hint)>) {
+		let piece = self.pieces[piece_idx];
+		if piece.used {
+			return;
+		}
+		let board = self.board();
+		let others = self.pieces.iter().enumerate().filter(|&(idx, other)| idx != piece_idx && !other.used);
+		let other_pieces = others.clone().count();
+
+		for (turns, shape) in self.orientations(piece.shape).into_iter().enumerate() {
+			for coord in board.size().coords() {
+				let Some(mask) = board.placement_mask(shape, coord) else {
+					continue;
+				};
+				if mask & board.bits() != 0 {
+					continue;
+				}
+
+				let cells = board.shape_cells(shape, coord).unwrap_or_default();
+				let contact = contact(board, &cells);
+
+				let mut after = board.clone();
+				after.fill(mask);
+				let lines_cleared = after.clear_lines().lines();
+
+				let pieces_fitting_after = others
+					.clone()
+					.filter(|(_, other)| self.orientations(other.shape).into_iter().any(|other| after.fits_anywhere(other)))
+					.count();
+
+				let hint = Hint {
+					piece_idx,
+					coord,
+					turns: turns as u8,
+					hold,
+					cells,
+					lines_cleared,
+					pieces_fitting_after,
+					other_pieces,
+				};
+				// Ties go to what is tried first: no swapping, then the fewest turns
+				let rank = (hint.keeps_room(), lines_cleared, pieces_fitting_after, contact);
+				if best.as_ref().is_none_or(|(best_rank, _)| rank > *best_rank) {
+					*best = Some((rank, hint));
+				}
+			}
+		}
+	}
+}
+
+/// Keeps room, lines cleared, pieces fitting after and contact, compared in that order.
+type HintRank = (bool, usize, usize, usize);
+
+/// Counts the edges of `cells` that touch a wall or a filled cell outside of `cells`.
+fn contact(board: &Board, cells: &[Coord]) -> usize {
+	let size = board.size();
+	cells
+		.iter()
+		.flat_map(|c| {
+			[
+				c.column.checked_sub(1).map(|column| Coord::new(column, c.row)),
+				c.row.checked_sub(1).map(|row| Coord::new(c.column, row)),
+				c.offset(1, 0, size),
+				c.offset(0, 1, size),
+			]
+		})
+		.filter(|neighbour| match neighbour {
+			Some(neighbour) => !cells.contains(neighbour) && board.filled(*neighbour),
+			None => true,
+		})
+		.count()
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::koala_kombo::{BoardSize, GRID_SIZE, Move, Piece, Shape};
+
+	fn game_with(board: Board, shapes: [Shape; 3]) -> KoalaKombo {
+		let mut game = KoalaKombo::builder().with_board(board).build();
+		game.pieces = shapes.map(|shape| Piece { shape, used: false });
+		game
+	}
+
+	#[test]
+	fn hint_clears_lines_test() {
+		let mut board = Board::default();
+		for column in 0..GRID_SIZE - 4 {
+			board.set(Coord::new(column, 3), true);
+		}
+		let mut game = game_with(board, [Shape::Single, Shape::Hero, Shape::Single]);
+
+		let hint = game.hint().unwrap();
+		assert_eq!(hint.piece_idx, 1);
+		assert_eq!(hint.coord, Coord::new(4, 3));
+		assert_eq!(hint.lines_cleared, 1);
+		assert!(hint.keeps_room());
+		assert!(game.place_shape(hint.piece_idx, hint.coord).is_ok());
+		assert_eq!(game.last_clear().rows, vec![3]);
+	}
+
+	#[test]
+	fn hint_keeps_room_test() {
+		// Only a 3x3 hole and a separate single hole are left
+		let mut board = Board::default();
+		for coord in board.size().coords() {
+			let in_hole = coord.column < 3 && coord.row < 3;
+			board.set(coord, !in_hole && coord != Coord::new(7, 7));
+		}
+		let mut game = game_with(board, [Shape::Huge, Shape::Single, Shape::Single]);
+		game.pieces[2].used = true;
+
+		// The single must not spoil the only spot the 3x3 fits into
+		let hint = game.hint().unwrap();
+		assert!(hint.keeps_room());
+		assert!(hint.piece_idx == 0 || hint.coord == Coord::new(7, 7));
+		assert!(hint.to_string().contains("keep room for the other pieces"));
+
+		let mut board = Board::default();
+		board.set(Coord::new(1, 1), true);
+		for coord in board.size().coords().filter(|c| c.column >= 3 || c.row >= 3) {
+			board.set(coord, true);
+		}
+		let game = game_with(board, [Shape::Huge; 3]);
+		assert_eq!(game.hint(), None);
+	}
+
+	#[test]
+	fn hint_rotates_test() {
+		let board = Board::new(BoardSize::new(1, 4));
+		let mut game = KoalaKombo::builder().with_board(board.clone()).build();
+		game.pieces = [Shape::Hero; 3].map(|shape| Piece { shape, used: false });
+		assert_eq!(game.hint(), None);
+
+		let mut game = KoalaKombo::builder().with_board(board).with_rotation(true).build();
+		game.pieces = [Shape::Hero; 3].map(|shape| Piece { shape, used: false });
+		assert!(!game.is_game_over());
+		let hint = game.hint().unwrap();
+		assert_eq!((hint.piece_idx, hint.coord, hint.turns), (0, Coord::new(0, 0), 1));
+		assert!(hint.to_string().starts_with("Turn piece 1 once and place it at column 1, row 1"));
+		let mv = Move {
+			turns: hint.turns,
+			..Move::new(hint.piece_idx, hint.coord)
+		};
+		assert!(game.play_move(mv).is_ok());
+	}
+
+	#[test]
+	fn hint_holds_test() {
+		let board = Board::new(BoardSize::new(4, 1));
+		let mut game = KoalaKombo::builder().with_board(board).with_hold(true).build();
+		game.pieces = [Shape::Hero, Shape::Huge, Shape::Huge].map(|shape| Piece { shape, used: false });
+		assert!(game.hold_piece(0));
+		assert!(!game.is_game_over());
+
+		let hint = game.hint().unwrap();
+		assert_eq!((hint.piece_idx, hint.coord, hint.hold), (0, Coord::new(0, 0), true));
+		assert!(hint.to_string().starts_with("Swap the held piece into slot 1 and place it"));
+		assert!(game.play_move(Move::hold(hint.piece_idx)).is_ok());
+		assert!(game.place_shape(hint.piece_idx, hint.coord).is_ok());
+	}
+}
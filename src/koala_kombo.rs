@@ -1,7 +1,94 @@
-use rand::RngExt;
+use std::{
+	error::Error,
+	fmt,
+	hash::{Hash, Hasher},
+	str::FromStr,
+	sync::Arc,
+};
 
+use rand::seq::SliceRandom;
+
+use crate::{
+	clock::Clock,
+	generator::{PieceGenerator, Uniform},
+	rng::GameRng,
+	scoring::{ClassicScoring, Combo, ScoringRules},
+};
+
+/// Width and height of the default square board.
 pub const GRID_SIZE: usize = 8;
 
+/// Number of columns and rows of a board, picked when the game starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BoardSize {
+	pub columns: usize,
+	pub rows: usize,
+}
+
+impl Default for BoardSize {
+	fn default() -> Self {
+		Self::square(GRID_SIZE)
+	}
+}
+
+impl BoardSize {
+	/// Largest number of cells a board can have, one per bit of the bitboard.
+	pub const MAX_CELLS: usize = u128::BITS as usize;
+
+	/// # Panics
+	/// If either dimension is zero or the board has more than [`BoardSize::MAX_CELLS`] cells.
+	pub const fn new(columns: usize, rows: usize) -> Self {
+		assert!(columns > 0 && rows > 0, "a board needs at least one column and one row");
+		let fits = match columns.checked_mul(rows) {
+			Some(cells) => cells <= Self::MAX_CELLS,
+			None => false,
+		};
+		assert!(fits, "the board has more cells than the bitboard can hold");
+		Self { columns, rows }
+	}
+
+	pub const fn square(size: usize) -> Self {
+		Self::new(size, size)
+	}
+
+	/// Total number of cells on the board.
+	pub const fn cells(self) -> usize {
+		self.columns * self.rows
+	}
+
+	/// Iterates over every coordinate on the board, row by row.
+	pub fn coords(self) -> impl Iterator<Item = Coord> {
+		(0..self.cells()).map(move |idx| Coord::from_index(idx, self))
+	}
+}
+
+impl fmt::Display for BoardSize {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}x{}", self.columns, self.rows)
+	}
+}
+
+/// Parses `10x8` as ten columns by eight rows, or a single number like `6` as a square board.
+impl FromStr for BoardSize {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let parse = |n: &str| match n.trim().parse::<usize>() {
+			Ok(n) if n > 0 => Ok(n),
+			_ => Err(format!("invalid board size `{s}`")),
+		};
+
+		let (columns, rows) = match s.split_once('x') {
+			Some((columns, rows)) => (parse(columns)?, parse(rows)?),
+			None => (parse(s)?, parse(s)?),
+		};
+		if columns.checked_mul(rows).is_none_or(|cells| cells > Self::MAX_CELLS) {
+			return Err(format!("board size `{s}` has more than {} cells", Self::MAX_CELLS));
+		}
+		Ok(Self::new(columns, rows))
+	}
+}
+
 /// Creates a `&'static [Coord]` from an ASCII grid.
 /// Use `x` for filled cells and `.` for empty cells.
 /// Wrap each row in `[]`.
@@ -55,22 +142,22 @@ impl Coord {
 		Self { column, row }
 	}
 
-	pub fn from_index(idx: usize) -> Self {
+	pub fn from_index(idx: usize, size: BoardSize) -> Self {
 		Self {
-			column: idx % GRID_SIZE,
-			row: idx / GRID_SIZE,
+			column: idx % size.columns,
+			row: idx / size.columns,
 		}
 	}
 
-	pub fn to_index(self) -> usize {
-		self.row * GRID_SIZE + self.column
+	pub fn to_index(self, size: BoardSize) -> usize {
+		self.row * size.columns + self.column
 	}
 
 	/// Returns the offset coordinate if it's within bounds, otherwise `None`.
-	pub fn offset(self, dc: usize, dr: usize) -> Option<Self> {
-		let column = self.column + dc;
-		let row = self.row + dr;
-		if column < GRID_SIZE && row < GRID_SIZE {
+	pub fn offset(self, dc: usize, dr: usize, size: BoardSize) -> Option<Self> {
+		let column = self.column.checked_add(dc)?;
+		let row = self.row.checked_add(dr)?;
+		if column < size.columns && row < size.rows {
 			Some(Self { column, row })
 		} else {
 			None
@@ -78,7 +165,7 @@ impl Coord {
 	}
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Shape {
 	// original tetris shapes
 	OrangeRicky,  // ▄▄█
@@ -142,6 +229,16 @@ pub enum Shape {
 	// ███
 	// ███
 	Huge,
+
+	// turned shapes, only reached by rotating or mirroring
+	OrangeRickyDown, // █▀▀
+	// ▀█
+	//  █
+	OrangeRickyLeft,
+	BlueRickyDown, // ▀▀█
+	//  █
+	// ▄█
+	BlueRickyLeft,
 }
 
 impl Shape {
@@ -171,8 +268,15 @@ impl Shape {
 		Shape::LongSmashboy,
 		Shape::LongSmashboyUp,
 		Shape::Huge,
+		Shape::OrangeRickyDown,
+		Shape::OrangeRickyLeft,
+		Shape::BlueRickyDown,
+		Shape::BlueRickyLeft,
 	];
 
+	/// Shapes trays are dealt from, every other shape is one of them turned.
+	pub const DEALT: &[Shape] = Shape::ALL.split_at(Shape::OrangeRickyDown as usize).0;
+
 	pub fn get_coords(&self) -> &'static [Coord] {
 		match self {
 			Shape::OrangeRicky => shape![
@@ -256,10 +360,96 @@ impl Shape {
 				[x x x]
 				[x x x]
 			],
+			Shape::OrangeRickyDown => shape![
+				[x x x]
+				[x . .]
+			],
+			Shape::OrangeRickyLeft => shape![
+				[x x]
+				[. x]
+				[. x]
+			],
+			Shape::BlueRickyDown => shape![
+				[x x x]
+				[. . x]
+			],
+			Shape::BlueRickyLeft => shape![
+				[. x]
+				[. x]
+				[x x]
+			],
+		}
+	}
+
+	/// Returns the shape with exactly these coordinates, in the order of [`Self::get_coords`].
+	pub fn from_coords(coords: &[Coord]) -> Option<Shape> {
+		Shape::ALL.iter().copied().find(|shape| shape.get_coords() == coords)
+	}
+
+	/// Returns the coordinates of the shape once transformed, moved back to the top left corner
+	/// and sorted row by row like [`Self::get_coords`].
+	pub fn transformed_coords(self, transform: Transform) -> Vec<Coord> {
+		let coords = self.get_coords();
+		let width = coords.iter().map(|c| c.column + 1).max().unwrap_or_default();
+		let height = coords.iter().map(|c| c.row + 1).max().unwrap_or_default();
+		let mut transformed = coords
+			.iter()
+			.map(|&Coord { column, row }| match transform {
+				Transform::Rotate90 => Coord::new(height - 1 - row, column),
+				Transform::Rotate180 => Coord::new(width - 1 - column, height - 1 - row),
+				Transform::Rotate270 => Coord::new(row, width - 1 - column),
+				Transform::FlipHorizontal => Coord::new(width - 1 - column, row),
+				Transform::FlipVertical => Coord::new(column, height - 1 - row),
+			})
+			.collect::<Vec<_>>();
+		transformed.sort_by_key(|c| (c.row, c.column));
+		transformed
+	}
+
+	/// Returns the shape that looks like this one transformed. Every shape turned or mirrored is a shape too.
+	pub fn transform(self, transform: Transform) -> Shape {
+		Shape::from_coords(&self.transformed_coords(transform)).expect("shapes turned or mirrored are shapes too")
+	}
+
+	/// Returns the distinct orientations of the shape clockwise, starting with the shape itself.
+	pub fn rotations(self) -> Vec<Shape> {
+		let mut rotations = vec![self];
+		let mut shape = self.rotated();
+		while shape != self {
+			rotations.push(shape);
+			shape = shape.rotated();
 		}
+		rotations
+	}
+
+	/// Returns the shape turned clockwise by 90°, the shape itself when that looks the same.
+	pub fn rotated(self) -> Shape {
+		self.transform(Transform::Rotate90)
 	}
 }
 
+/// Ways to turn or mirror a shape. Rotations are clockwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transform {
+	Rotate90,
+	Rotate180,
+	Rotate270,
+	/// Mirrors left and right.
+	FlipHorizontal,
+	/// Mirrors top and bottom.
+	FlipVertical,
+}
+
+impl Transform {
+	pub const ALL: &[Transform] = &[
+		Transform::Rotate90,
+		Transform::Rotate180,
+		Transform::Rotate270,
+		Transform::FlipHorizontal,
+		Transform::FlipVertical,
+	];
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Piece {
 	pub shape: Shape,
@@ -267,111 +457,1542 @@ pub struct Piece {
 }
 
 impl Piece {
-	fn random() -> Self {
-		let idx = rand::rng().random_range(0..Shape::ALL.len());
+	/// Deals a fresh tray of three unused pieces.
+	/// Once the generator runs out the remaining slots stay empty, which plays like an already used piece.
+	fn deal(generator: &mut dyn PieceGenerator, rng: &mut GameRng, board: &Board) -> [Self; 3] {
+		std::array::from_fn(|_| {
+			if generator.has_next() {
+				Self {
+					shape: generator.next_shape(rng, board),
+					used: false,
+				}
+			} else {
+				Self {
+					shape: Shape::Single,
+					used: true,
+				}
+			}
+		})
+	}
+}
+
+/// What a cell of the board holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+	Empty,
+	/// Part of a placed piece, emptied when one of its lines is cleared.
+	Filled,
+	/// Never cleared, but counts toward completing its lines.
+	Stone,
+	/// Takes two clears to remove, the first one only cracks it.
+	Ice,
+	/// Ice that was cleared once and melts with the next clear.
+	CrackedIce,
+	/// Can't be placed on and keeps its lines from completing.
+	/// Opens once this many more lines were cleared anywhere on the board.
+	Locked(u32),
+}
+
+/// Obstacles scattered over the empty cells of a new board.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Obstacles {
+	pub stones: usize,
+	pub ice: usize,
+	pub locked: usize,
+	/// Lines to clear before a locked cell opens.
+	pub lock_clears: u32,
+}
+
+/// Which cells of the board are occupied, stored as a bitboard.
+/// Bit `row * columns + column` is set for every occupied cell, so fit checks, placement
+/// and line detection are a handful of bitwise operations against precomputed masks.
+/// Obstacles are occupied cells that are also set in one of the obstacle layers.
+#[derive(Debug, Clone)]
+pub struct Board {
+	size: BoardSize,
+	bits: u128,
+	stones: u128,
+	ice: u128,
+	cracked: u128,
+	/// Cell index and lines left to clear of every locked cell, ordered by index.
+	locks: Vec<(usize, u32)>,
+	masks: Arc<Masks>,
+}
+
+/// Masks that only depend on the board size, shared by every copy of a board.
+#[derive(Debug)]
+struct Masks {
+	/// `placements[shape as usize][idx]` covers the shape with its top left at cell `idx`,
+	/// `None` where the shape would stick out of the board.
+	placements: Vec<Vec<Option<u128>>>,
+	rows: Vec<u128>,
+	columns: Vec<u128>,
+}
+
+impl Masks {
+	fn new(size: BoardSize) -> Self {
+		let placements = Shape::ALL
+			.iter()
+			.map(|shape| size.coords().map(|coord| Board::coords_mask(shape.get_coords(), coord, size)).collect())
+			.collect();
+		let rows = (0..size.rows)
+			.map(|row| (0..size.columns).fold(0, |mask, column| mask | Board::bit(Coord::new(column, row), size)))
+			.collect();
+		let columns = (0..size.columns)
+			.map(|column| (0..size.rows).fold(0, |mask, row| mask | Board::bit(Coord::new(column, row), size)))
+			.collect();
+
 		Self {
-			shape: Shape::ALL[idx],
-			used: false,
+			placements,
+			rows,
+			columns,
+		}
+	}
+}
+
+impl Default for Board {
+	fn default() -> Self {
+		Self::new(BoardSize::default())
+	}
+}
+
+impl PartialEq for Board {
+	fn eq(&self, other: &Self) -> bool {
+		self.size == other.size
+			&& self.bits == other.bits
+			&& self.stones == other.stones
+			&& self.ice == other.ice
+			&& self.cracked == other.cracked
+			&& self.locks == other.locks
+	}
+}
+
+impl Eq for Board {}
+
+impl Hash for Board {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.size.hash(state);
+		self.bits.hash(state);
+		self.stones.hash(state);
+		self.ice.hash(state);
+		self.cracked.hash(state);
+		self.locks.hash(state);
+	}
+}
+
+impl Board {
+	pub fn new(size: BoardSize) -> Self {
+		Self {
+			size,
+			bits: 0,
+			stones: 0,
+			ice: 0,
+			cracked: 0,
+			locks: Vec::new(),
+			masks: Arc::new(Masks::new(size)),
+		}
+	}
+
+	pub fn size(&self) -> BoardSize {
+		self.size
+	}
+
+	/// Returns the raw bitboard of every occupied cell, obstacles included.
+	pub fn bits(&self) -> u128 {
+		self.bits
+	}
+
+	fn bit(coord: Coord, size: BoardSize) -> u128 {
+		1 << coord.to_index(size)
+	}
+
+	/// Builds the mask of `coords` offset by `origin`, `None` if any cell would be out of bounds.
+	fn coords_mask(coords: &[Coord], origin: Coord, size: BoardSize) -> Option<u128> {
+		coords.iter().try_fold(0, |mask, delta| Some(mask | Self::bit(origin.offset(delta.column, delta.row, size)?, size)))
+	}
+
+	/// Returns if a cell on board is occupied at a given coordinate, by a piece or an obstacle.
+	pub fn filled(&self, coord: Coord) -> bool {
+		self.bits & Self::bit(coord, self.size) != 0
+	}
+
+	pub fn set(&mut self, coord: Coord, filled: bool) {
+		self.set_cell(coord, if filled { Cell::Filled } else { Cell::Empty });
+	}
+
+	pub fn cell(&self, coord: Coord) -> Cell {
+		let bit = Self::bit(coord, self.size);
+		if self.bits & bit == 0 {
+			Cell::Empty
+		} else if self.stones & bit != 0 {
+			Cell::Stone
+		} else if self.ice & bit != 0 {
+			Cell::Ice
+		} else if self.cracked & bit != 0 {
+			Cell::CrackedIce
+		} else {
+			match self.locks.binary_search_by_key(&coord.to_index(self.size), |&(idx, _)| idx) {
+				Ok(i) => Cell::Locked(self.locks[i].1),
+				Err(_) => Cell::Filled,
+			}
+		}
+	}
+
+	/// Replaces whatever the cell held. A lock waiting for no more lines is an empty cell.
+	pub fn set_cell(&mut self, coord: Coord, cell: Cell) {
+		let idx = coord.to_index(self.size);
+		let bit = Self::bit(coord, self.size);
+		self.bits &= !bit;
+		self.stones &= !bit;
+		self.ice &= !bit;
+		self.cracked &= !bit;
+		if let Ok(i) = self.locks.binary_search_by_key(&idx, |&(idx, _)| idx) {
+			self.locks.remove(i);
+		}
+
+		match cell {
+			Cell::Empty | Cell::Locked(0) => return,
+			Cell::Filled => {},
+			Cell::Stone => self.stones |= bit,
+			Cell::Ice => self.ice |= bit,
+			Cell::CrackedIce => self.cracked |= bit,
+			Cell::Locked(clears) => {
+				let i = self.locks.partition_point(|&(other, _)| other < idx);
+				self.locks.insert(i, (idx, clears));
+			},
+		}
+		self.bits |= bit;
+	}
+
+	/// Scatters the obstacles over random empty cells, at most half of them so there is room left to play.
+	pub fn scatter(&mut self, obstacles: Obstacles, rng: &mut GameRng) {
+		let mut empty = self.size.coords().filter(|&coord| !self.filled(coord)).collect::<Vec<_>>();
+		let count = (obstacles.stones + obstacles.ice + obstacles.locked).min(empty.len() / 2);
+		let (picked, _) = empty.partial_shuffle(rng, count);
+		let cells = std::iter::repeat_n(Cell::Stone, obstacles.stones)
+			.chain(std::iter::repeat_n(Cell::Ice, obstacles.ice))
+			.chain(std::iter::repeat_n(Cell::Locked(obstacles.lock_clears), obstacles.locked));
+		for (&coord, cell) in picked.iter().zip(cells) {
+			self.set_cell(coord, cell);
+		}
+	}
+
+	/// Returns the mask of the locked cells.
+	fn locked(&self) -> u128 {
+		self.locks.iter().fold(0, |mask, &(idx, _)| mask | 1 << idx)
+	}
+
+	/// Returns the precomputed mask of a shape placed at the coordinate.
+	/// Returns `None` if any cell would be out of bounds.
+	pub fn placement_mask(&self, shape: Shape, coord: Coord) -> Option<u128> {
+		if coord.column >= self.size.columns || coord.row >= self.size.rows {
+			return None;
+		}
+		self.masks.placements[shape as usize][coord.to_index(self.size)]
+	}
+
+	/// Get the cells a shape would cover if placed at the coordinate.
+	/// Returns `None` if any cell would be out of bounds.
+	pub fn shape_cells(&self, shape: Shape, coord: Coord) -> Option<Vec<Coord>> {
+		shape.get_coords().iter().map(|delta| coord.offset(delta.column, delta.row, self.size)).collect()
+	}
+
+	/// Returns if the shape fits at the coordinate without leaving the board or overlapping filled cells.
+	pub fn fits(&self, shape: Shape, coord: Coord) -> bool {
+		self.placement_mask(shape, coord).is_some_and(|mask| mask & self.bits == 0)
+	}
+
+	/// Returns if the shape fits anywhere on the board.
+	pub fn fits_anywhere(&self, shape: Shape) -> bool {
+		self.masks.placements[shape as usize].iter().flatten().any(|&mask| mask & self.bits == 0)
+	}
+
+	/// Fills every cell of the mask.
+	pub fn fill(&mut self, mask: u128) {
+		self.bits |= mask;
+	}
+
+	/// Detects all full rows and columns first and only then clears them,
+	/// so a row and column crossing at the placed piece are both cleared.
+	/// Stones in cleared lines stay, ice cracks, and every line cleared brings locked cells closer to opening.
+	/// Points are left at `0` for the caller to fill in from the scoring rules.
+	pub fn clear_lines(&mut self) -> ClearReport {
+		// Locked cells don't complete a line, and a line of nothing but stones would clear forever
+		let open = self.bits & !self.locked();
+		let full = |masks: &[u128]| {
+			(0..masks.len()).filter(|&i| open & masks[i] == masks[i] && masks[i] & !self.stones != 0).collect::<Vec<_>>()
+		};
+		let rows = full(&self.masks.rows);
+		let columns = full(&self.masks.columns);
+
+		let cleared = rows
+			.iter()
+			.map(|&row| self.masks.rows[row])
+			.chain(columns.iter().map(|&column| self.masks.columns[column]))
+			.fold(0, |cleared, mask| cleared | mask);
+		let cracking = cleared & self.ice;
+		let emptied = cleared & !self.stones & !cracking;
+		let cells = self.size.coords().filter(|&coord| emptied & Self::bit(coord, self.size) != 0).collect();
+		self.bits &= !emptied;
+		self.ice &= !cracking;
+		self.cracked = (self.cracked & !emptied) | cracking;
+
+		let lines = (rows.len() + columns.len()) as u32;
+		if lines > 0 {
+			for (idx, clears) in &mut self.locks {
+				*clears = clears.saturating_sub(lines);
+				if *clears == 0 {
+					self.bits &= !(1 << *idx);
+				}
+			}
+			self.locks.retain(|&(_, clears)| clears > 0);
+		}
+
+		ClearReport {
+			rows,
+			columns,
+			cells,
+			points: 0,
+		}
+	}
+}
+
+/// The lines removed by a single placement, detected together against the board before anything was cleared.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClearReport {
+	pub rows: Vec<usize>,
+	pub columns: Vec<usize>,
+	/// Every cell that was emptied, each listed once even where a row and column cross.
+	pub cells: Vec<Coord>,
+	pub points: u32,
+}
+
+impl ClearReport {
+	/// Total number of rows and columns cleared.
+	pub fn lines(&self) -> usize {
+		self.rows.len() + self.columns.len()
+	}
+}
+
+/// Why a piece could not be placed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlacementError {
+	/// Part of the piece would stick out of the board.
+	OutOfBounds,
+	/// The piece would cover these already filled cells.
+	Overlaps(Vec<Coord>),
+	/// The piece was already placed from the current tray.
+	PieceAlreadyUsed,
+	/// There is no piece at this tray index.
+	InvalidPieceIndex,
+	/// The clock of a timed game ran out.
+	OutOfTime,
+	/// The piece can't be turned, as the game doesn't allow it.
+	CannotRotate,
+	/// The piece can't go to or come from the hold slot.
+	CannotHold,
+}
+
+impl fmt::Display for PlacementError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			PlacementError::OutOfBounds => write!(f, "piece does not fit on the board"),
+			PlacementError::Overlaps(cells) => write!(f, "piece overlaps {} filled cell(s)", cells.len()),
+			PlacementError::PieceAlreadyUsed => write!(f, "piece was already placed"),
+			PlacementError::InvalidPieceIndex => write!(f, "no piece at this tray index"),
+			PlacementError::OutOfTime => write!(f, "time is up"),
+			PlacementError::CannotRotate => write!(f, "pieces can't be rotated in this game"),
+			PlacementError::CannotHold => write!(f, "piece can't be held"),
+		}
+	}
+}
+
+impl Error for PlacementError {}
+
+/// A placement of the piece at `piece_idx` of the tray, with its top left at `coord`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+	pub piece_idx: usize,
+	pub coord: Coord,
+	/// Times the piece was rotated clockwise since it was dealt, see [`KoalaKombo::rotate_piece`].
+	pub turns: u8,
+	/// Whether the piece went to the hold slot instead of the board, see [`KoalaKombo::hold_piece`].
+	/// `coord` means nothing then.
+	pub hold: bool,
+}
+
+impl Move {
+	/// A placement of the piece as it was dealt.
+	pub const fn new(piece_idx: usize, coord: Coord) -> Self {
+		Self {
+			piece_idx,
+			coord,
+			turns: 0,
+			hold: false,
+		}
+	}
+
+	/// Swapping the piece as it was dealt with the hold slot.
+	pub const fn hold(piece_idx: usize) -> Self {
+		Self {
+			hold: true,
+			..Self::new(piece_idx, Coord::new(0, 0))
+		}
+	}
+}
+
+/// Everything a successful placement did to the game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlacementOutcome {
+	/// The cells the piece was placed on.
+	pub cells: Vec<Coord>,
+	/// The lines the placement cleared.
+	pub clear: ClearReport,
+	/// All points earned, for the placed cells as well as the cleared lines.
+	pub points: u32,
+	/// Whether the placement used up the tray and a new one was dealt.
+	pub refilled: bool,
+}
+
+/// How many moves a player may take back.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UndoPolicy {
+	#[default]
+	Unlimited,
+	/// At most this many undos per game.
+	Limited(u32),
+	Disabled,
+}
+
+/// The state a move changes, kept so the move can be undone and redone exactly.
+#[derive(Debug, Clone)]
+struct Snapshot {
+	board: Board,
+	pieces: [Piece; 3],
+	turns: [u8; 3],
+	held: Option<Shape>,
+	score: u32,
+	last_clear: ClearReport,
+	combo: Combo,
+	rng: GameRng,
+	generator: Box<dyn PieceGenerator>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct History {
+	policy: UndoPolicy,
+	undo: Vec<Snapshot>,
+	redo: Vec<Snapshot>,
+	/// Moves taken back, in the order [`Self::redo`] plays them again.
+	redo_moves: Vec<Move>,
+	undos_used: u32,
+}
+
+impl History {
+	/// Number of undos still allowed, `None` when unlimited.
+	fn undos_left(&self) -> Option<u32> {
+		match self.policy {
+			UndoPolicy::Unlimited => None,
+			UndoPolicy::Limited(limit) => Some(limit.saturating_sub(self.undos_used)),
+			UndoPolicy::Disabled => Some(0),
+		}
+	}
+
+	/// Records the state before a new move. A new move discards everything that could have been redone.
+	fn record(&mut self, snapshot: Snapshot) {
+		self.redo.clear();
+		self.redo_moves.clear();
+		if let Some(left) = self.undos_left() {
+			// Older moves can never be reached again
+			if left == 0 {
+				return;
+			}
+			if self.undo.len() >= left as usize {
+				self.undo.remove(0);
+			}
+		}
+		self.undo.push(snapshot);
+	}
+}
+
+/// Configures a game before it starts.
+/// Anything left unset falls back to an entropy seed, [`ClassicScoring`] and the [`Uniform`] generator.
+#[derive(Debug, Default)]
+pub struct KoalaKomboBuilder {
+	seed: Option<u64>,
+	size: BoardSize,
+	board: Option<Board>,
+	rules: Option<Arc<dyn ScoringRules>>,
+	generator: Option<Box<dyn PieceGenerator>>,
+	undo: UndoPolicy,
+	clock: Option<Clock>,
+	rotation: bool,
+	hold: bool,
+	obstacles: Option<Obstacles>,
+}
+
+impl KoalaKomboBuilder {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Deals every tray from the given seed, so the same seed always deals the same pieces.
+	pub fn with_seed(mut self, seed: u64) -> Self {
+		self.seed = Some(seed);
+		self
+	}
+
+	pub fn with_size(mut self, size: BoardSize) -> Self {
+		self.size = size;
+		self
+	}
+
+	/// Starts on a board that may already have filled cells. Takes precedence over [`Self::with_size`].
+	pub fn with_board(mut self, board: Board) -> Self {
+		self.board = Some(board);
+		self
+	}
+
+	pub fn with_scoring(mut self, rules: impl ScoringRules + 'static) -> Self {
+		self.rules = Some(Arc::new(rules));
+		self
+	}
+
+	pub fn with_generator(mut self, generator: impl PieceGenerator + 'static) -> Self {
+		self.generator = Some(Box::new(generator));
+		self
+	}
+
+	pub fn with_undo(mut self, undo: UndoPolicy) -> Self {
+		self.undo = undo;
+		self
+	}
+
+	/// Plays against the clock: the game ends when it runs out.
+	pub fn with_clock(mut self, clock: Clock) -> Self {
+		self.clock = Some(clock);
+		self
+	}
+
+	/// Lets the player turn tray pieces before placing them.
+	pub fn with_rotation(mut self, rotation: bool) -> Self {
+		self.rotation = rotation;
+		self
+	}
+
+	/// Adds a hold slot to park a tray piece for later.
+	pub fn with_hold(mut self, hold: bool) -> Self {
+		self.hold = hold;
+		self
+	}
+
+	/// Scatters obstacles over the starting board, drawn from the seed like the pieces.
+	pub fn with_obstacles(mut self, obstacles: Obstacles) -> Self {
+		self.obstacles = Some(obstacles);
+		self
+	}
+
+	pub fn build(self) -> KoalaKombo {
+		let mut rng = self.seed.map_or_else(GameRng::from_entropy, GameRng::new);
+		let mut generator = self.generator.unwrap_or_else(|| Box::new(Uniform));
+		let mut board = self.board.unwrap_or_else(|| Board::new(self.size));
+		if let Some(obstacles) = self.obstacles {
+			board.scatter(obstacles, &mut rng);
+		}
+
+		KoalaKombo {
+			pieces: Piece::deal(generator.as_mut(), &mut rng, &board),
+			board,
+			score: 0,
+			last_clear: ClearReport::default(),
+			rules: self.rules.unwrap_or_else(|| Arc::new(ClassicScoring::default())),
+			combo: Combo::default(),
+			rng,
+			generator,
+			history: History {
+				policy: self.undo,
+				..Default::default()
+			},
+			moves: Vec::new(),
+			clock: self.clock,
+			rotation: self.rotation,
+			turns: [0; 3],
+			hold: self.hold,
+			held: None,
 		}
 	}
 }
 
 #[derive(Debug, Clone)]
 pub struct KoalaKombo {
-	board: [bool; GRID_SIZE * GRID_SIZE],
+	board: Board,
 	pub pieces: [Piece; 3],
 	pub score: u32,
+	last_clear: ClearReport,
+	rules: Arc<dyn ScoringRules>,
+	combo: Combo,
+	rng: GameRng,
+	generator: Box<dyn PieceGenerator>,
+	history: History,
+	/// Every placement since the start, minus the ones taken back.
+	moves: Vec<Move>,
+	/// Time left in a timed game. Undo doesn't turn it back.
+	clock: Option<Clock>,
+	/// Whether tray pieces may be rotated.
+	rotation: bool,
+	/// Times each tray piece was rotated since it was dealt.
+	turns: [u8; 3],
+	/// Whether the game has a hold slot.
+	hold: bool,
+	/// The piece parked in the hold slot.
+	held: Option<Shape>,
+}
+
+impl Default for KoalaKombo {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 
 impl KoalaKombo {
 	pub fn new() -> Self {
-		Self {
-			board: [false; GRID_SIZE * GRID_SIZE],
-			pieces: [Piece::random(), Piece::random(), Piece::random()],
-			score: 0,
-		}
+		KoalaKomboBuilder::new().build()
+	}
+
+	/// Creates a game whose trays are all drawn from the given seed, so the same seed always deals the same pieces.
+	pub fn with_seed(seed: u64) -> Self {
+		KoalaKomboBuilder::new().with_seed(seed).build()
+	}
+
+	/// Creates a game that is scored by the given rules.
+	pub fn with_scoring(rules: impl ScoringRules + 'static) -> Self {
+		KoalaKomboBuilder::new().with_scoring(rules).build()
+	}
+
+	pub fn builder() -> KoalaKomboBuilder {
+		KoalaKomboBuilder::new()
+	}
+
+	pub fn board(&self) -> &Board {
+		&self.board
+	}
+
+	pub fn size(&self) -> BoardSize {
+		self.board.size
+	}
+
+	/// Returns the seed of this run, which reproduces it when passed to [`KoalaKombo::with_seed`].
+	pub fn seed(&self) -> u64 {
+		self.rng.seed()
+	}
+
+	/// Returns the current combo streak, `0` when the last moves cleared nothing.
+	pub fn combo(&self) -> u32 {
+		self.combo.streak
+	}
+
+	/// Returns what the most recent placement cleared; empty if it cleared nothing.
+	pub fn last_clear(&self) -> &ClearReport {
+		&self.last_clear
+	}
+
+	/// Returns every placement of this game in order, without the ones that were undone.
+	pub fn moves(&self) -> &[Move] {
+		&self.moves
 	}
 
 	/// Returns if a cell on board is filled at a given coordinate.
 	pub fn cell_filled(&self, coord: Coord) -> bool {
-		self.board[coord.to_index()]
+		self.board.filled(coord)
 	}
 
 	/// Get the cells that would be occupied if placing a piece at the coordinate.
-	/// Returns `None` if any cell would be out of bounds.
+	/// Returns `None` if any cell would be out of bounds or there is no piece at the index.
 	pub fn can_place(&self, piece_idx: usize, coord: Coord) -> Option<Vec<Coord>> {
-		let piece = &self.pieces[piece_idx];
+		self.board.shape_cells(self.pieces.get(piece_idx)?.shape, coord)
+	}
+
+	/// Returns if the piece fits at the coordinate without leaving the board or overlapping filled cells.
+	pub fn fits(&self, piece_idx: usize, coord: Coord) -> bool {
+		self.pieces.get(piece_idx).is_some_and(|piece| self.board.fits(piece.shape, coord))
+	}
+
+	/// Checks everything [`KoalaKombo::place_shape`] checks without changing the game.
+	/// Returns the cells the piece would be placed on.
+	pub fn check_placement(&self, piece_idx: usize, coord: Coord) -> Result<Vec<Coord>, PlacementError> {
+		self.placement_mask(piece_idx, coord)?;
+		Ok(self.board.shape_cells(self.pieces[piece_idx].shape, coord).unwrap_or_default())
+	}
+
+	/// Checks the placement, returning the mask of the cells the piece would be placed on.
+	/// The overlapping cells are only looked up once the mask shows there are some.
+	fn placement_mask(&self, piece_idx: usize, coord: Coord) -> Result<u128, PlacementError> {
+		if self.is_out_of_time() {
+			return Err(PlacementError::OutOfTime);
+		}
+		let piece = self.pieces.get(piece_idx).ok_or(PlacementError::InvalidPieceIndex)?;
+		if piece.used {
+			return Err(PlacementError::PieceAlreadyUsed);
+		}
+
+		let mask = self.board.placement_mask(piece.shape, coord).ok_or(PlacementError::OutOfBounds)?;
+		if mask & self.board.bits != 0 {
+			let cells = self.board.shape_cells(piece.shape, coord).unwrap_or_default();
+			return Err(PlacementError::Overlaps(cells.into_iter().filter(|&c| self.board.filled(c)).collect()));
+		}
 
-		piece.shape.get_coords().iter().map(|delta| coord.offset(delta.column, delta.row)).collect::<Option<Vec<Coord>>>()
+		Ok(mask)
 	}
 
-	/// Place a piece on the board. Returns true if successful.
+	/// Returns if any unused piece in the tray, or the held one, can still be placed somewhere on the board.
+	/// The held piece counts since it can always be swapped back into the tray.
+	pub fn has_valid_move(&self) -> bool {
+		let fits = |shape: Shape| self.orientations(shape).into_iter().any(|shape| self.board.fits_anywhere(shape));
+		self.pieces.iter().filter(|piece| !piece.used).map(|piece| piece.shape).chain(self.held).any(fits)
+	}
+
+	/// Returns if the game has ended because none of the unused pieces fit anywhere, or its clock ran out.
+	pub fn is_game_over(&self) -> bool {
+		self.is_out_of_time() || !self.has_valid_move()
+	}
+
+	/// The clock of a timed game, `None` when the game is untimed.
+	pub fn clock(&self) -> Option<&Clock> {
+		self.clock.as_ref()
+	}
+
+	pub fn clock_mut(&mut self) -> Option<&mut Clock> {
+		self.clock.as_mut()
+	}
+
+	fn is_out_of_time(&self) -> bool {
+		self.clock.is_some_and(|clock| clock.is_expired())
+	}
+
+	/// Place a piece on the board.
 	/// Handles: placement, marking used, clearing lines, score, and regenerating pieces.
-	pub fn place_shape(&mut self, piece_idx: usize, coord: Coord) -> bool {
-		let cells = match self.can_place(piece_idx, coord) {
-			Some(cells) if cells.iter().all(|&c| !self.board[c.to_index()]) => cells,
-			_ => return false,
-		};
+	pub fn place_shape(&mut self, piece_idx: usize, coord: Coord) -> Result<PlacementOutcome, PlacementError> {
+		let mask = self.placement_mask(piece_idx, coord)?;
+		let snapshot = self.snapshot();
+		self.history.record(snapshot);
 
 		// Place the blocks
-		for &c in &cells {
-			self.board[c.to_index()] = true;
-		}
+		let cells = self.board.shape_cells(self.pieces[piece_idx].shape, coord).unwrap_or_default();
+		self.board.fill(mask);
 
 		// Mark piece as used
 		self.pieces[piece_idx].used = true;
+		self.moves.push(Move {
+			turns: self.turns[piece_idx],
+			..Move::new(piece_idx, coord)
+		});
 
 		// Clear complete lines and update score
-		self.score += self.clear_lines();
+		let mut report = self.board.clear_lines();
+		let streak = self.combo.record(report.lines(), self.rules.combo_grace());
+		report.points = self.rules.clear_points(report.lines(), streak);
+		let points = self.rules.placement_points(cells.len()) + report.points;
+		self.score += points;
+		self.last_clear = report.clone();
+		if let Some(clock) = &mut self.clock {
+			clock.reward_lines(report.lines());
+		}
 
-		// Regenerate pieces if all used
-		if self.pieces.iter().all(|p| p.used) {
-			self.pieces = [Piece::random(), Piece::random(), Piece::random()];
+		let refilled = self.refill();
+
+		Ok(PlacementOutcome {
+			cells,
+			clear: report,
+			points,
+			refilled,
+		})
+	}
+
+	/// Deals a new tray once every piece of it is used, a held piece not counting. Returns if it did.
+	fn refill(&mut self) -> bool {
+		let empty = self.pieces.iter().all(|p| p.used);
+		if empty {
+			self.pieces = Piece::deal(self.generator.as_mut(), &mut self.rng, &self.board);
+			self.turns = [0; 3];
 		}
+		empty
+	}
+
+	/// Returns if the game has a hold slot.
+	pub fn can_hold(&self) -> bool {
+		self.hold
+	}
 
+	/// The piece in the hold slot, `None` while it's empty.
+	pub fn held(&self) -> Option<Shape> {
+		self.held
+	}
+
+	/// Parks an unused tray piece in the hold slot, swapping it with the piece held so far,
+	/// or takes the held piece back into an empty slot of the tray.
+	/// Returns if a piece moved. Holding counts as a move, so it can be undone and replayed.
+	pub fn hold_piece(&mut self, piece_idx: usize) -> bool {
+		let Some(&piece) = self.pieces.get(piece_idx) else {
+			return false;
+		};
+		if !self.hold || self.is_out_of_time() || (piece.used && self.held.is_none()) {
+			return false;
+		}
+
+		let snapshot = self.snapshot();
+		self.history.record(snapshot);
+		self.moves.push(Move {
+			turns: self.turns[piece_idx],
+			..Move::hold(piece_idx)
+		});
+
+		let taken = self.held.take();
+		if !piece.used {
+			self.held = Some(piece.shape);
+		}
+		self.pieces[piece_idx] = match taken {
+			Some(shape) => Piece { shape, used: false },
+			// The slot stays empty until the tray is dealt again
+			None => Piece { used: true, ..piece },
+		};
+		self.turns[piece_idx] = 0;
+		self.refill();
 		true
 	}
 
-	fn clear_lines(&mut self) -> u32 {
-		let mut score = 0;
+	/// Plays a recorded move: turns the piece as it was turned, then places or holds it.
+	/// Returns what the placement did, `None` for holding.
+	pub fn play_move(&mut self, mv: Move) -> Result<Option<PlacementOutcome>, PlacementError> {
+		for _ in 0..mv.turns {
+			if !self.rotate_piece(mv.piece_idx) {
+				return Err(PlacementError::CannotRotate);
+			}
+		}
+		if !mv.hold {
+			self.place_shape(mv.piece_idx, mv.coord).map(Some)
+		} else if self.hold_piece(mv.piece_idx) {
+			Ok(None)
+		} else {
+			Err(PlacementError::CannotHold)
+		}
+	}
+
+	/// Returns if the rules let the player rotate tray pieces.
+	pub fn can_rotate(&self) -> bool {
+		self.rotation
+	}
+
+	/// Returns the orientations a piece of the shape can be placed in, the one reached with `n` clockwise turns at `n`.
+	/// Just the shape itself unless the rules allow rotation.
+	pub fn orientations(&self, shape: Shape) -> Vec<Shape> {
+		if self.rotation { shape.rotations() } else { vec![shape] }
+	}
 
-		// Check rows
-		for row in 0..GRID_SIZE {
-			let start = row * GRID_SIZE;
-			if self.board[start..start + GRID_SIZE].iter().all(|&filled| filled) {
-				for column in 0..GRID_SIZE {
-					self.board[Coord::new(column, row).to_index()] = false;
+	/// Turns an unused tray piece to its next orientation clockwise, see [`Shape::rotated`].
+	/// Returns if the piece changed; rotating is free and doesn't count as a move.
+	pub fn rotate_piece(&mut self, piece_idx: usize) -> bool {
+		let Some(piece) = self.pieces.get_mut(piece_idx).filter(|piece| self.rotation && !piece.used) else {
+			return false;
+		};
+		let rotations = piece.shape.rotations();
+		if rotations.len() == 1 {
+			return false;
+		}
+		piece.shape = rotations[1];
+		self.turns[piece_idx] = (self.turns[piece_idx] + 1) % rotations.len() as u8;
+		true
+	}
+
+	pub fn can_undo(&self) -> bool {
+		!self.history.undo.is_empty() && self.history.undos_left() != Some(0)
+	}
+
+	pub fn can_redo(&self) -> bool {
+		!self.history.redo.is_empty()
+	}
+
+	/// Number of undos the game still allows, `None` when unlimited.
+	pub fn undos_left(&self) -> Option<u32> {
+		self.history.undos_left()
+	}
+
+	/// Takes back the last move, restoring the board, tray, score and RNG exactly as they were before it.
+	/// Returns if there was a move to undo.
+	pub fn undo(&mut self) -> bool {
+		if !self.can_undo() {
+			return false;
+		}
+
+		let snapshot = self.history.undo.pop().unwrap();
+		let current = self.snapshot();
+		self.history.redo.push(current);
+		self.history.redo_moves.extend(self.moves.pop());
+		self.history.undos_used += 1;
+		self.restore(snapshot);
+		true
+	}
+
+	/// Plays the last undone move again. Returns if there was a move to redo.
+	pub fn redo(&mut self) -> bool {
+		let Some(snapshot) = self.history.redo.pop() else {
+			return false;
+		};
+		self.moves.extend(self.history.redo_moves.pop());
+
+		let current = self.snapshot();
+		self.history.undo.push(current);
+		self.restore(snapshot);
+		true
+	}
+
+	fn snapshot(&self) -> Snapshot {
+		Snapshot {
+			board: self.board.clone(),
+			pieces: self.pieces,
+			turns: self.turns,
+			held: self.held,
+			score: self.score,
+			last_clear: self.last_clear.clone(),
+			combo: self.combo,
+			rng: self.rng.clone(),
+			generator: self.generator.clone(),
+		}
+	}
+
+	fn restore(&mut self, snapshot: Snapshot) {
+		self.board = snapshot.board;
+		self.pieces = snapshot.pieces;
+		self.turns = snapshot.turns;
+		self.held = snapshot.held;
+		self.score = snapshot.score;
+		self.last_clear = snapshot.last_clear;
+		self.combo = snapshot.combo;
+		self.rng = snapshot.rng;
+		self.generator = snapshot.generator;
+	}
+}
+
+/// Saving and loading a game in progress through Fyrox's [`Visit`](fyrox::core::visitor::Visit).
+/// Only progress is stored: scoring rules, generator configuration and undo policy are kept from the
+/// game that is visited into, and undo history starts empty after loading.
+#[cfg(feature = "gui")]
+mod visit {
+	use fyrox::core::visitor::prelude::*;
+
+	use super::*;
+
+	impl Visit for Shape {
+		fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+			let mut idx = *self as u32;
+			idx.visit(name, visitor)?;
+			*self = *Shape::ALL.get(idx as usize).ok_or_else(|| VisitError::User(format!("invalid shape {idx}")))?;
+			Ok(())
+		}
+	}
+
+	impl Visit for Piece {
+		fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+			let mut region = visitor.enter_region(name)?;
+			self.shape.visit("Shape", &mut region)?;
+			self.used.visit("Used", &mut region)
+		}
+	}
+
+	/// Visits a bitboard split in two, the visitor has no 128 bit fields.
+	fn visit_mask(mask: &mut u128, name: &str, visitor: &mut Visitor) -> VisitResult {
+		let mut low = *mask as u64;
+		let mut high = (*mask >> 64) as u64;
+		low.visit(&format!("{name}Low"), visitor)?;
+		high.visit(&format!("{name}High"), visitor)?;
+		*mask = low as u128 | (high as u128) << 64;
+		Ok(())
+	}
+
+	impl Visit for Board {
+		fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+			let mut region = visitor.enter_region(name)?;
+			let mut columns = self.size.columns as u32;
+			let mut rows = self.size.rows as u32;
+			let mut bits = self.bits;
+			columns.visit("Columns", &mut region)?;
+			rows.visit("Rows", &mut region)?;
+			visit_mask(&mut bits, "", &mut region)?;
+
+			let (mut stones, mut ice, mut cracked) = (self.stones, self.ice, self.cracked);
+			let (mut lock_cells, mut lock_clears): (Vec<u32>, Vec<u32>) =
+				self.locks.iter().map(|&(idx, clears)| (idx as u32, clears)).unzip();
+			match visit_mask(&mut stones, "Stones", &mut region) {
+				// Boards saved before obstacles have none
+				Err(VisitError::FieldDoesNotExist(_)) if region.is_reading() => {
+					(stones, ice, cracked) = (0, 0, 0);
+					lock_cells.clear();
+				},
+				result => {
+					result?;
+					visit_mask(&mut ice, "Ice", &mut region)?;
+					visit_mask(&mut cracked, "Cracked", &mut region)?;
+					lock_cells.visit("LockCells", &mut region)?;
+					lock_clears.visit("LockClears", &mut region)?;
+				},
+			}
+
+			if region.is_reading() {
+				let (columns, rows) = (columns as usize, rows as usize);
+				if columns == 0 || rows == 0 || columns * rows > BoardSize::MAX_CELLS {
+					return Err(VisitError::User(format!("invalid board size {columns}x{rows}")));
 				}
-				score += GRID_SIZE as u32;
+				let size = BoardSize::new(columns, rows);
+				if size != self.size {
+					*self = Board::new(size);
+				}
+				self.bits = bits & self.masks.rows.iter().fold(0, |all, row| all | row);
+				// Every layer only keeps occupied cells no other layer claims
+				self.stones = stones & self.bits;
+				self.ice = ice & self.bits & !self.stones;
+				self.cracked = cracked & self.bits & !self.stones & !self.ice;
+				let obstacles = self.stones | self.ice | self.cracked;
+				self.locks = lock_cells
+					.iter()
+					.zip(&lock_clears)
+					.map(|(&idx, &clears)| (idx as usize, clears))
+					.filter(|&(idx, clears)| idx < columns * rows && clears > 0 && (self.bits & !obstacles) >> idx & 1 != 0)
+					.collect();
+				self.locks.sort_unstable();
+				self.locks.dedup_by_key(|&mut (idx, _)| idx);
 			}
+			Ok(())
 		}
+	}
 
-		// Check columns
-		for column in 0..GRID_SIZE {
-			if (0..GRID_SIZE).all(|row| self.board[Coord::new(column, row).to_index()]) {
-				for row in 0..GRID_SIZE {
-					self.board[Coord::new(column, row).to_index()] = false;
-				}
-				score += GRID_SIZE as u32;
+	impl Visit for Move {
+		fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+			let mut region = visitor.enter_region(name)?;
+			let mut piece_idx = self.piece_idx as u32;
+			let mut column = self.coord.column as u32;
+			let mut row = self.coord.row as u32;
+			let mut turns = self.turns;
+			piece_idx.visit("Piece", &mut region)?;
+			column.visit("Column", &mut region)?;
+			row.visit("Row", &mut region)?;
+			let mut hold = self.hold;
+			// Saves from before rotation and holding have neither
+			if turns.visit("Turns", &mut region).is_err() {
+				turns = 0;
 			}
+			if hold.visit("Hold", &mut region).is_err() {
+				hold = false;
+			}
+			if region.is_reading() {
+				*self = Move {
+					piece_idx: piece_idx as usize,
+					coord: Coord::new(column as usize, row as usize),
+					turns,
+					hold,
+				};
+			}
+			Ok(())
 		}
+	}
+
+	impl Visit for KoalaKombo {
+		fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+			let mut region = visitor.enter_region(name)?;
+			self.board.visit("Board", &mut region)?;
+			for (i, piece) in self.pieces.iter_mut().enumerate() {
+				piece.visit(&format!("Piece{i}"), &mut region)?;
+			}
+			self.score.visit("Score", &mut region)?;
+			self.combo.visit("Combo", &mut region)?;
+			self.rng.visit("Rng", &mut region)?;
+			self.generator.visit_state("Generator", &mut region)?;
+			self.history.undos_used.visit("UndosUsed", &mut region)?;
+			// Only games with rotation have turns, so older saves still load
+			if self.rotation {
+				for (i, turns) in self.turns.iter_mut().enumerate() {
+					turns.visit(&format!("Turns{i}"), &mut region)?;
+				}
+			}
+			// Likewise only games with a hold slot have a held piece
+			if self.hold {
+				let mut held = self.held.is_some();
+				let mut shape = self.held.unwrap_or(Shape::Single);
+				held.visit("Held", &mut region)?;
+				shape.visit("HeldShape", &mut region)?;
+				self.held = held.then_some(shape);
+			}
+
+			let mut moves = self.moves.len() as u32;
+			moves.visit("MoveCount", &mut region)?;
+			if region.is_reading() {
+				self.moves = vec![Move::new(0, Coord::new(0, 0)); moves as usize];
+			}
+			for (i, mv) in self.moves.iter_mut().enumerate() {
+				mv.visit(&format!("Move{i}"), &mut region)?;
+			}
 
-		score
+			if region.is_reading() {
+				self.last_clear = ClearReport::default();
+				self.history.undo.clear();
+				self.history.redo.clear();
+				self.history.redo_moves.clear();
+			}
+			Ok(())
+		}
 	}
 }
 
 #[cfg(test)]
 mod test {
 	use super::*;
+	use crate::generator::Bag;
 
 	#[test]
 	fn new_test() {
 		let game = KoalaKombo::new();
 		assert_eq!(game.score, 0);
-		assert_eq!(game.board.len(), GRID_SIZE * GRID_SIZE);
+		assert_eq!(game.size(), BoardSize::square(GRID_SIZE));
+		assert_eq!(game.board.bits(), 0);
 		assert_eq!(game.pieces.len(), 3);
 	}
 
+	#[test]
+	fn seeded_game_test() {
+		let shapes = |game: &KoalaKombo| game.pieces.map(|p| p.shape);
+
+		let mut a = KoalaKombo::with_seed(42);
+		let mut b = KoalaKombo::with_seed(42);
+		assert_eq!(a.seed(), 42);
+		assert_eq!(shapes(&a), shapes(&b));
+
+		// Use up the tray on both games so they refill from their own RNG
+		for game in [&mut a, &mut b] {
+			for piece in &mut game.pieces {
+				piece.shape = Shape::Single;
+			}
+			for column in 0..3 {
+				assert!(game.place_shape(column, Coord::new(column, 0)).is_ok());
+			}
+		}
+		assert_eq!(shapes(&a), shapes(&b));
+		assert_eq!(a.seed(), b.seed());
+	}
+
+	#[test]
+	fn placement_errors_test() {
+		let mut game = KoalaKombo::new();
+		game.pieces = [Piece {
+			shape: Shape::Duce,
+			used: false,
+		}; 3];
+		game.board.set(Coord::new(3, 3), true);
+
+		assert_eq!(game.place_shape(3, Coord::new(0, 0)), Err(PlacementError::InvalidPieceIndex));
+		assert_eq!(game.place_shape(0, Coord::new(7, 0)), Err(PlacementError::OutOfBounds));
+		assert_eq!(game.place_shape(0, Coord::new(2, 3)), Err(PlacementError::Overlaps(vec![Coord::new(3, 3)])));
+
+		let outcome = game.place_shape(0, Coord::new(0, 0)).unwrap();
+		assert_eq!(outcome.cells, vec![Coord::new(0, 0), Coord::new(1, 0)]);
+		assert_eq!(outcome.clear, ClearReport::default());
+		assert_eq!(outcome.points, 2);
+		assert!(!outcome.refilled);
+		assert_eq!(game.place_shape(0, Coord::new(0, 5)), Err(PlacementError::PieceAlreadyUsed));
+
+		// Failed placements leave the game untouched
+		assert_eq!(game.score, 2);
+		assert!(!game.cell_filled(Coord::new(2, 3)));
+
+		assert!(!game.place_shape(1, Coord::new(0, 1)).unwrap().refilled);
+		assert!(game.place_shape(2, Coord::new(0, 2)).unwrap().refilled);
+	}
+
+	#[test]
+	fn exhausted_generator_test() {
+		use crate::generator::Sequence;
+
+		let mut game = KoalaKombo::builder().with_generator(Sequence::new([Shape::Single; 4])).build();
+		assert!(game.pieces.iter().all(|p| p.shape == Shape::Single && !p.used));
+		for column in 0..3 {
+			assert!(game.place_shape(column, Coord::new(column, 0)).is_ok());
+		}
+
+		// Only one shape was left for the second tray, the other slots stay empty
+		assert_eq!(game.pieces.map(|p| p.used), [false, true, true]);
+		assert!(!game.is_game_over());
+		assert!(game.place_shape(0, Coord::new(0, 1)).unwrap().refilled);
+		assert!(game.pieces.iter().all(|p| p.used));
+		assert!(game.is_game_over());
+	}
+
+	#[test]
+	fn undo_redo_test() {
+		let mut game = KoalaKombo::builder().with_seed(3).with_generator(Bag::default()).build();
+		for piece in &mut game.pieces {
+			piece.shape = Shape::Single;
+		}
+		for column in 1..GRID_SIZE {
+			game.board.set(Coord::new(column, 0), true);
+		}
+		assert!(!game.can_undo());
+		assert!(!game.undo());
+
+		let start = game.clone();
+		assert!(game.place_shape(0, Coord::new(0, 0)).is_ok());
+		assert!(game.place_shape(1, Coord::new(0, 1)).is_ok());
+		// This placement refills the tray from the RNG and the bag
+		assert!(game.place_shape(2, Coord::new(0, 2)).unwrap().refilled);
+		let end = game.clone();
+
+		assert!(game.undo());
+		assert!(game.pieces.iter().all(|p| p.shape == Shape::Single));
+		assert!(game.pieces[0].used && game.pieces[1].used && !game.pieces[2].used);
+		assert!(game.undo());
+		assert!(game.undo());
+		assert!(!game.undo());
+		assert_eq!(game.board, start.board);
+		assert_eq!(game.score, start.score);
+		assert_eq!(game.combo(), start.combo());
+		assert!(game.can_redo());
+
+		while game.redo() {}
+		assert_eq!(game.board, end.board);
+		assert_eq!(game.score, end.score);
+		assert_eq!(game.pieces.map(|p| p.shape), end.pieces.map(|p| p.shape));
+
+		// Undoing the refill and playing on deals the very same tray again
+		assert!(game.undo());
+		assert!(game.place_shape(2, Coord::new(0, 2)).is_ok());
+		assert_eq!(game.pieces.map(|p| p.shape), end.pieces.map(|p| p.shape));
+		assert!(!game.can_redo());
+	}
+
+	#[test]
+	fn undo_policy_test() {
+		let mut game = KoalaKombo::builder().with_undo(UndoPolicy::Limited(1)).build();
+		game.pieces = [Piece {
+			shape: Shape::Single,
+			used: false,
+		}; 3];
+		assert_eq!(game.undos_left(), Some(1));
+		assert!(game.place_shape(0, Coord::new(0, 0)).is_ok());
+		assert!(game.place_shape(1, Coord::new(1, 0)).is_ok());
+		assert!(game.undo());
+		assert_eq!(game.undos_left(), Some(0));
+		assert!(!game.can_undo());
+		assert!(!game.undo());
+
+		// Redo stays available even when no undos are left
+		assert!(game.redo());
+		assert!(game.cell_filled(Coord::new(1, 0)));
+
+		let mut game = KoalaKombo::builder().with_undo(UndoPolicy::Disabled).build();
+		game.pieces[0].shape = Shape::Single;
+		assert!(game.place_shape(0, Coord::new(0, 0)).is_ok());
+		assert!(!game.can_undo());
+		assert!(!game.undo());
+		assert_eq!(game.undos_left(), Some(0));
+	}
+
+	#[test]
+	fn game_over_test() {
+		let mut game = KoalaKombo::new();
+		game.pieces = [
+			Piece {
+				shape: Shape::Huge,
+				used: false,
+			},
+			Piece {
+				shape: Shape::Single,
+				used: true,
+			},
+			Piece {
+				shape: Shape::LongHero,
+				used: false,
+			},
+		];
+		assert!(game.has_valid_move());
+		assert!(!game.is_game_over());
+
+		// Checkerboard: no two neighbouring cells are free, so nothing bigger than a single fits
+		for coord in game.size().coords() {
+			game.board.set(coord, (coord.column + coord.row).is_multiple_of(2));
+		}
+		assert!(!game.has_valid_move());
+		assert!(game.is_game_over());
+
+		// The used single must not count as a playable piece
+		game.pieces[1].used = false;
+		assert!(game.has_valid_move());
+		assert!(game.fits(1, Coord::new(1, 0)));
+		assert!(!game.fits(1, Coord::new(0, 0)));
+	}
+
+	#[test]
+	fn clear_crossing_lines_test() {
+		let mut game = KoalaKombo::new();
+		game.pieces[0] = Piece {
+			shape: Shape::Single,
+			used: false,
+		};
+
+		// Fill row 2 and column 5 except for the cell where they cross
+		for i in 0..GRID_SIZE {
+			game.board.set(Coord::new(i, 2), true);
+			game.board.set(Coord::new(5, i), true);
+		}
+		game.board.set(Coord::new(5, 2), false);
+		game.board.set(Coord::new(0, 7), true);
+
+		assert!(game.place_shape(0, Coord::new(5, 2)).is_ok());
+		let report = game.last_clear();
+		assert_eq!(report.rows, vec![2]);
+		assert_eq!(report.columns, vec![5]);
+		assert_eq!(report.lines(), 2);
+		assert_eq!(report.cells.len(), GRID_SIZE * 2 - 1);
+		assert_eq!(report.points, 32);
+		assert_eq!(game.score, 33);
+		assert_eq!(game.combo(), 1);
+
+		// Only the unrelated cell survives
+		let filled = game.size().coords().filter(|&c| game.cell_filled(c)).collect::<Vec<_>>();
+		assert_eq!(filled, vec![Coord::new(0, 7)]);
+	}
+
+	#[test]
+	fn combo_scoring_test() {
+		let mut game = KoalaKombo::builder()
+			.with_scoring(ClassicScoring {
+				cell_points: 1,
+				line_points: 10,
+				combo_grace: 1,
+			})
+			.build();
+		game.pieces = [Piece {
+			shape: Shape::Single,
+			used: false,
+		}; 3];
+		for column in 1..GRID_SIZE {
+			game.board.set(Coord::new(column, 0), true);
+			game.board.set(Coord::new(column, 1), true);
+		}
+
+		// First clear starts the streak
+		assert!(game.place_shape(0, Coord::new(0, 0)).is_ok());
+		assert_eq!(game.combo(), 1);
+		assert_eq!(game.score, 1 + 10);
+
+		// Back-to-back clear doubles the line points
+		assert!(game.place_shape(1, Coord::new(0, 1)).is_ok());
+		assert_eq!(game.combo(), 2);
+		assert_eq!(game.score, 11 + 1 + 20);
+
+		// A move without a clear ends the streak with a grace of one
+		assert!(game.place_shape(2, Coord::new(4, 4)).is_ok());
+		assert_eq!(game.combo(), 0);
+		assert_eq!(game.score, 32 + 1);
+	}
+
+	#[test]
+	fn board_size_test() {
+		assert_eq!("10x8".parse(), Ok(BoardSize::new(10, 8)));
+		assert_eq!("6".parse(), Ok(BoardSize::square(6)));
+		assert!("0x8".parse::<BoardSize>().is_err());
+		assert!("wide".parse::<BoardSize>().is_err());
+		assert!("12x11".parse::<BoardSize>().is_err());
+		assert!("4294967296x4294967296".parse::<BoardSize>().is_err());
+		assert_eq!(BoardSize::new(10, 8).to_string(), "10x8");
+
+		let size = BoardSize::new(10, 8);
+		assert_eq!(Coord::from_index(23, size), Coord::new(3, 2));
+		assert_eq!(Coord::new(3, 2).to_index(size), 23);
+		assert_eq!(Coord::new(9, 0).offset(0, 7, size), Some(Coord::new(9, 7)));
+		assert_eq!(Coord::new(9, 0).offset(1, 0, size), None);
+		assert_eq!(Coord::new(0, 7).offset(0, 1, size), None);
+		assert_eq!(Coord::new(usize::MAX, 0).offset(1, 0, size), None);
+	}
+
+	#[test]
+	fn non_square_board_test() {
+		let mut game = KoalaKombo::builder().with_size(BoardSize::new(10, 6)).build();
+		game.pieces = [Piece {
+			shape: Shape::Single,
+			used: false,
+		}; 3];
+		assert!(!game.fits(0, Coord::new(0, 6)));
+		assert!(game.fits(0, Coord::new(9, 5)));
+
+		// A full row is ten cells wide and a full column six cells high
+		for column in 1..10 {
+			game.board.set(Coord::new(column, 5), true);
+		}
+		for row in 0..5 {
+			game.board.set(Coord::new(0, row), true);
+		}
+
+		assert!(game.place_shape(0, Coord::new(0, 5)).is_ok());
+		let report = game.last_clear();
+		assert_eq!(report.rows, vec![5]);
+		assert_eq!(report.columns, vec![0]);
+		assert_eq!(report.cells.len(), 10 + 6 - 1);
+		assert!(game.size().coords().all(|c| !game.cell_filled(c)));
+
+		// Shapes that fit on the default board may not fit on a small one at all
+		let game = KoalaKombo::builder().with_size(BoardSize::square(2)).build();
+		assert!(!game.board().fits_anywhere(Shape::Huge));
+		assert!(game.board().fits_anywhere(Shape::Smashboy));
+	}
+
+	#[test]
+	fn bitboard_test() {
+		// Placement masks are looked up by discriminant, so `ALL` has to list shapes in declaration order
+		for (idx, &shape) in Shape::ALL.iter().enumerate() {
+			assert_eq!(shape as usize, idx);
+		}
+
+		let mut board = Board::default();
+		assert_eq!(board.placement_mask(Shape::Smashboy, Coord::new(0, 0)), Some(0b11 | 0b11 << GRID_SIZE));
+		assert_eq!(board.placement_mask(Shape::Smashboy, Coord::new(7, 0)), None);
+		assert_eq!(board.placement_mask(Shape::LongHeroUp, Coord::new(0, 4)), None);
+
+		board.set(Coord::new(1, 1), true);
+		assert_eq!(board.bits(), 1 << (GRID_SIZE + 1));
+		assert!(board.filled(Coord::new(1, 1)));
+		assert!(!board.fits(Shape::Smashboy, Coord::new(0, 0)));
+		assert!(board.fits(Shape::Smashboy, Coord::new(2, 0)));
+		board.set(Coord::new(1, 1), false);
+		assert_eq!(board, Board::default());
+
+		// The largest board still fits into the bitboard, including the last bit
+		let size = BoardSize::new(16, 8);
+		let mut board = Board::new(size);
+		board.set(Coord::new(15, 7), true);
+		assert_eq!(board.bits(), 1 << 127);
+		assert!(!board.fits(Shape::Single, Coord::new(15, 7)));
+		assert!(board.fits_anywhere(Shape::LongHero));
+	}
+
+	#[test]
+	fn clock_test() {
+		let single = Piece {
+			shape: Shape::Single,
+			used: false,
+		};
+		let mut game =
+			KoalaKombo::builder().with_size(BoardSize::new(3, 3)).with_clock(Clock::new(5.0).with_line_bonus(3.0)).build();
+		game.pieces = [single; 3];
+		assert!(game.place_shape(0, Coord::new(0, 0)).is_ok());
+		assert!(game.place_shape(1, Coord::new(1, 0)).is_ok());
+		let outcome = game.place_shape(2, Coord::new(2, 0)).unwrap();
+		assert_eq!(outcome.clear.lines(), 1);
+		assert_eq!(game.clock().map(Clock::remaining), Some(8.0));
+
+		// Time running out ends the game, even with room left on the board
+		assert!(!game.is_game_over());
+		assert!(game.clock_mut().unwrap().tick(8.0));
+		assert!(game.has_valid_move());
+		assert!(game.is_game_over());
+		assert_eq!(game.place_shape(0, Coord::new(0, 0)), Err(PlacementError::OutOfTime));
+	}
+
+	#[test]
+	fn transform_test() {
+		assert_eq!(Shape::Hero.transform(Transform::Rotate90), Shape::HeroUp);
+		assert_eq!(Shape::Teewee.rotations(), [Shape::Teewee, Shape::TeeweeRight, Shape::TeeweeDown, Shape::TeeweeLeft]);
+		assert_eq!(Shape::OrangeRicky.transform(Transform::FlipHorizontal), Shape::BlueRicky);
+		assert_eq!(Shape::ClevelandZ.transform(Transform::FlipVertical), Shape::RhodeIslandZ);
+		assert_eq!(Shape::Huge.rotated(), Shape::Huge);
+
+		// Turned coordinates start at the top left again
+		assert_eq!(
+			Shape::OrangeRicky.transformed_coords(Transform::Rotate180),
+			[Coord::new(0, 0), Coord::new(1, 0), Coord::new(2, 0), Coord::new(0, 1)]
+		);
+		assert_eq!(Shape::OrangeRicky.transform(Transform::Rotate180), Shape::OrangeRickyDown);
+		assert_eq!(
+			Shape::OrangeRicky.rotations(),
+			[
+				Shape::OrangeRicky,
+				Shape::OrganeRickyUp,
+				Shape::OrangeRickyDown,
+				Shape::OrangeRickyLeft
+			]
+		);
+		assert_eq!(Shape::BlueRickyLeft.rotated(), Shape::BlueRicky);
+		assert_eq!(Shape::ClevelandZ.rotations(), [Shape::ClevelandZ, Shape::ClevelandZUp]);
+
+		for &shape in Shape::ALL {
+			for &transform in Transform::ALL {
+				let coords = shape.transformed_coords(transform);
+				assert_eq!(shape.transform(transform).get_coords(), coords);
+				assert_eq!(coords.len(), shape.get_coords().len());
+				assert_eq!(coords.iter().map(|c| c.column).min(), Some(0), "{shape:?} {transform:?}");
+				assert_eq!(coords.iter().map(|c| c.row).min(), Some(0), "{shape:?} {transform:?}");
+			}
+			let rotations = shape.rotations();
+			assert_eq!(rotations.iter().map(|r| r.rotated()).collect::<Vec<_>>()[rotations.len() - 1], shape);
+		}
+	}
+
+	#[test]
+	fn rotate_piece_test() {
+		let mut game = KoalaKombo::builder().with_size(BoardSize::new(4, 1)).build();
+		game.pieces[0] = Piece {
+			shape: Shape::Hero,
+			used: false,
+		};
+		assert!(!game.rotate_piece(0));
+
+		let mut game = KoalaKombo::from_ascii("[. . . .]\n[. . . .]\n\n[x]\n[x]\n[x]\n[x]\n\n[o]\n\n[o]").unwrap();
+		assert!(game.is_game_over());
+		game.rotation = true;
+		assert!(!game.is_game_over());
+		assert!(!game.rotate_piece(1));
+		assert!(game.rotate_piece(0));
+		assert_eq!(game.pieces[0].shape, Shape::Hero);
+		let outcome = game.place_shape(0, Coord::new(0, 0)).unwrap();
+		assert_eq!(outcome.clear.lines(), 1);
+		assert_eq!(game.moves()[0].turns, 1);
+	}
+
+	#[test]
+	fn hold_test() {
+		let piece = |shape| Piece { shape, used: false };
+		let mut game = KoalaKombo::builder().with_size(BoardSize::new(5, 5)).build();
+		assert!(!game.hold_piece(0));
+
+		let mut game = KoalaKombo::builder().with_size(BoardSize::new(5, 5)).with_hold(true).build();
+		game.pieces = [piece(Shape::Single), piece(Shape::Duce), piece(Shape::Hero)];
+		assert!(game.hold_piece(0));
+		assert_eq!(game.held(), Some(Shape::Single));
+		assert!(game.pieces[0].used);
+		assert_eq!(game.moves(), [Move::hold(0)]);
+
+		// Holding another piece swaps it with the held one
+		assert!(game.hold_piece(1));
+		assert_eq!(game.held(), Some(Shape::Duce));
+		assert_eq!((game.pieces[1].shape, game.pieces[1].used), (Shape::Single, false));
+		assert!(!game.hold_piece(3));
+
+		// The held piece doesn't keep the tray from being dealt again
+		assert!(game.place_shape(1, Coord::new(0, 0)).is_ok());
+		assert!(game.place_shape(2, Coord::new(0, 4)).unwrap().refilled);
+		assert_eq!(game.held(), Some(Shape::Duce));
+
+		game.pieces = [piece(Shape::Single); 3];
+		assert!(game.place_shape(0, Coord::new(4, 0)).is_ok());
+		assert!(game.hold_piece(0));
+		assert_eq!((game.pieces[0].shape, game.pieces[0].used), (Shape::Duce, false));
+		assert_eq!(game.held(), None);
+		assert!(game.undo());
+		assert_eq!(game.held(), Some(Shape::Duce));
+
+		// Nothing in the tray fits, but the held piece still does
+		let mut game = KoalaKombo::from_ascii("[x .]\n[. x]\n\n[x x]\n\n[o]\n\n[o]").unwrap();
+		assert!(game.is_game_over());
+		game.hold = true;
+		game.held = Some(Shape::Single);
+		assert!(!game.is_game_over());
+	}
+
 	#[test]
 	fn shape_macro_coords() {
 		// OrangeRicky: ..x / xxx → (2,0), (0,1), (1,1), (2,1)
@@ -416,4 +2037,66 @@ mod test {
 		assert!(coords.contains(&Coord::new(2, 0)));
 		assert!(coords.contains(&Coord::new(1, 1)));
 	}
+
+	#[test]
+	fn obstacles_test() {
+		let mut board = Board::new(BoardSize::new(4, 4));
+		for (column, row, cell) in [
+			(0, 0, Cell::Stone),
+			(1, 0, Cell::Ice),
+			(2, 0, Cell::Filled),
+			(0, 1, Cell::CrackedIce),
+			(1, 1, Cell::Filled),
+			(2, 1, Cell::Filled),
+			(0, 2, Cell::Locked(2)),
+			(1, 2, Cell::Filled),
+			(2, 2, Cell::Filled),
+		] {
+			board.set_cell(Coord::new(column, row), cell);
+		}
+		let mut game = KoalaKombo::builder().with_board(board).build();
+		game.pieces = [Piece {
+			shape: Shape::Single,
+			used: false,
+		}; 3];
+
+		// The locked cell keeps its row from completing
+		assert_eq!(game.place_shape(0, Coord::new(3, 2)).unwrap().clear.lines(), 0);
+		assert!(!game.fits(1, Coord::new(0, 2)));
+
+		// Stones stay, ice cracks and the lock counts down
+		let clear = game.place_shape(1, Coord::new(3, 0)).unwrap().clear;
+		assert_eq!(clear.cells, [Coord::new(2, 0), Coord::new(3, 0)]);
+		assert_eq!(game.board().cell(Coord::new(0, 0)), Cell::Stone);
+		assert_eq!(game.board().cell(Coord::new(1, 0)), Cell::CrackedIce);
+		assert_eq!(game.board().cell(Coord::new(0, 2)), Cell::Locked(1));
+
+		// Cracked ice melts, and the lock opens with the next line
+		assert_eq!(game.place_shape(2, Coord::new(3, 1)).unwrap().clear.lines(), 1);
+		assert_eq!(game.board().cell(Coord::new(0, 1)), Cell::Empty);
+		assert_eq!(game.board().cell(Coord::new(0, 2)), Cell::Empty);
+		assert!(game.undo());
+		assert_eq!(game.board().cell(Coord::new(0, 2)), Cell::Locked(1));
+
+		// A line of nothing but stones is never cleared
+		let mut board = Board::new(BoardSize::new(2, 2));
+		board.set_cell(Coord::new(0, 0), Cell::Stone);
+		board.set_cell(Coord::new(1, 0), Cell::Stone);
+		assert_eq!(board.clear_lines().lines(), 0);
+		board.set_cell(Coord::new(0, 0), Cell::Locked(0));
+		assert_eq!(board.cell(Coord::new(0, 0)), Cell::Empty);
+
+		let obstacles = Obstacles {
+			stones: 2,
+			ice: 3,
+			locked: 1,
+			lock_clears: 4,
+		};
+		let game = KoalaKombo::builder().with_seed(3).with_obstacles(obstacles).build();
+		let cells = game.size().coords().map(|coord| game.board().cell(coord)).collect::<Vec<_>>();
+		assert_eq!(cells.iter().filter(|&&cell| cell == Cell::Stone).count(), 2);
+		assert_eq!(cells.iter().filter(|&&cell| cell == Cell::Ice).count(), 3);
+		assert_eq!(cells.iter().filter(|&&cell| cell == Cell::Locked(4)).count(), 1);
+		assert_eq!(KoalaKombo::builder().with_seed(3).with_obstacles(obstacles).build().board(), game.board());
+	}
 }
@@ -1,16 +1,35 @@
-mod koala_kombo;
+pub mod clock;
+pub mod daily;
+pub mod date;
+pub mod generator;
+pub mod highscores;
+pub mod hint;
+pub mod koala_kombo;
+pub mod mode;
+pub mod notation;
+#[cfg(feature = "gui")]
 mod plugin;
+pub mod puzzle;
+pub mod replay;
+pub mod rng;
+#[cfg(feature = "gui")]
+pub mod save;
+pub mod scoring;
+pub mod solver;
+pub mod storage;
 
+#[cfg(feature = "gui")]
 pub use plugin::GamePlugin;
 
+#[cfg(feature = "gui")]
 pub fn run_game() {
 	GamePlugin::run_game();
 }
 
-#[cfg(target_arch = "wasm32")]
+#[cfg(all(feature = "gui", target_arch = "wasm32"))]
 use wasm_bindgen::prelude::*;
 
-#[cfg(target_arch = "wasm32")]
+#[cfg(all(feature = "gui", target_arch = "wasm32"))]
 #[wasm_bindgen(start)]
 pub fn wasm_main() {
 	run_game();
@@ -0,0 +1,104 @@
+use std::{fmt, str::FromStr};
+
+use crate::{
+	clock::Clock,
+	koala_kombo::{KoalaKombo, KoalaKomboBuilder, Obstacles, UndoPolicy},
+};
+
+/// Seconds on the clock at the start of a blitz game.
+const BLITZ_SECONDS: f32 = 90.0;
+/// Seconds a blitz game gains for every line it clears.
+const BLITZ_LINE_BONUS: f32 = 3.0;
+/// Obstacles on the board at the start of an obstacles game.
+const OBSTACLES: Obstacles = Obstacles {
+	stones: 3,
+	ice: 4,
+	locked: 3,
+	lock_clears: 5,
+};
+
+/// The ways to play. Each mode decides the rules a new game is built with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GameMode {
+	/// Endless play with random trays until nothing fits anymore.
+	#[default]
+	Classic,
+	/// Hand-made boards with a fixed run of pieces and a goal, see [`Puzzle`](crate::puzzle::Puzzle).
+	/// The puzzle sets up board and pieces, so games of this mode are built by it.
+	Puzzle,
+	/// One game a day dealt from the date, see [`daily`](crate::daily). Without undo, so scores compare fairly.
+	Daily,
+	/// Classic play against a countdown that cleared lines top up. Undo would only take back the board, not the time.
+	Blitz,
+	/// Classic play where tray pieces can be rotated before they are placed.
+	Twist,
+	/// Classic play with a hold slot to park a tray piece for later.
+	Hold,
+	/// Classic play on a board that starts out with stones, ice and locked cells.
+	Obstacles,
+}
+
+impl GameMode {
+	pub const ALL: &[GameMode] = &[
+		GameMode::Classic,
+		GameMode::Puzzle,
+		GameMode::Daily,
+		GameMode::Blitz,
+		GameMode::Twist,
+		GameMode::Hold,
+		GameMode::Obstacles,
+	];
+
+	pub fn name(self) -> &'static str {
+		match self {
+			GameMode::Classic => "classic",
+			GameMode::Puzzle => "puzzle",
+			GameMode::Daily => "daily",
+			GameMode::Blitz => "blitz",
+			GameMode::Twist => "twist",
+			GameMode::Hold => "hold",
+			GameMode::Obstacles => "obstacles",
+		}
+	}
+
+	pub fn undo_policy(self) -> UndoPolicy {
+		match self {
+			GameMode::Classic | GameMode::Puzzle | GameMode::Twist | GameMode::Hold | GameMode::Obstacles => {
+				UndoPolicy::Unlimited
+			},
+			GameMode::Daily | GameMode::Blitz => UndoPolicy::Disabled,
+		}
+	}
+
+	/// Returns if an unfinished game of this mode is saved to be resumed on the next launch.
+	/// Puzzles can't be rebuilt from a save, and a daily challenge or a blitz game ends when it is left.
+	pub fn can_resume(self) -> bool {
+		matches!(self, GameMode::Classic | GameMode::Twist | GameMode::Hold | GameMode::Obstacles)
+	}
+
+	/// Returns a builder set up with this mode's rules, ready for further configuration.
+	pub fn builder(self) -> KoalaKomboBuilder {
+		let builder = KoalaKombo::builder().with_undo(self.undo_policy());
+		match self {
+			GameMode::Blitz => builder.with_clock(Clock::new(BLITZ_SECONDS).with_line_bonus(BLITZ_LINE_BONUS)),
+			GameMode::Twist => builder.with_rotation(true),
+			GameMode::Hold => builder.with_hold(true),
+			GameMode::Obstacles => builder.with_obstacles(OBSTACLES),
+			_ => builder,
+		}
+	}
+}
+
+impl fmt::Display for GameMode {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.name())
+	}
+}
+
+impl FromStr for GameMode {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		GameMode::ALL.iter().copied().find(|mode| mode.name() == s).ok_or_else(|| format!("unknown game mode `{s}`"))
+	}
+}
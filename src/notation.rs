@@ -0,0 +1,297 @@
+//! Text notation for positions, in the `x` and `.` grids of the `shape!` macro.
+//!
+//! The board comes first, one `[...]` line per row, followed by the pieces of the tray.
+//! Every grid is separated from the next by a blank line, and used pieces are drawn with `o`.
+//! Boards may hold obstacles: `#` for stones, `*` for ice, `~` for cracked ice, and for locked cells
+//! the number of lines left to clear before they open:
+//!
+//! ```text
+//! [. # . 3]
+//! [x x * x]
+//!
+//! [x x]
+//! [x x]
+//!
+//! [o o o]
+//!
+//! [x]
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use crate::koala_kombo::{Board, BoardSize, Cell, Coord, KoalaKombo, Piece, Shape};
+
+/// Cell of a grid, as written in the notation.
+const FILLED: char = 'x';
+const EMPTY: char = '.';
+/// Cell of a piece that was already placed from the tray.
+const USED: char = 'o';
+/// Obstacles of a board.
+const STONE: char = '#';
+const ICE: char = '*';
+const CRACKED_ICE: char = '~';
+
+/// A cell of a grid: one of the marks above, or the lines a locked cell waits for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Mark {
+	Char(char),
+	Lock(u32),
+}
+
+impl fmt::Display for Mark {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Mark::Char(c) => write!(f, "{c}"),
+			Mark::Lock(clears) => write!(f, "{clears}"),
+		}
+	}
+}
+
+pub(crate) type Grid = Vec<Vec<Mark>>;
+
+/// Splits the text into its grids, skipping blank lines around them.
+pub(crate) fn grids(s: &str) -> Result<Vec<Grid>, String> {
+	let mut grids = Vec::new();
+	let mut grid = Grid::new();
+	for line in s.lines().map(str::trim) {
+		if line.is_empty() {
+			if !grid.is_empty() {
+				grids.push(std::mem::take(&mut grid));
+			}
+			continue;
+		}
+
+		let row = line
+			.strip_prefix('[')
+			.and_then(|line| line.strip_suffix(']'))
+			.ok_or_else(|| format!("expected a `[...]` row, found `{line}`"))?;
+		let cells = row
+			.split_whitespace()
+			.map(|cell| {
+				let mut chars = cell.chars();
+				match (chars.next(), chars.next()) {
+					(Some(c @ (FILLED | EMPTY | USED | STONE | ICE | CRACKED_ICE)), None) => Ok(Mark::Char(c)),
+					_ if cell.bytes().all(|b| b.is_ascii_digit()) => match cell.parse() {
+						Ok(clears) if clears > 0 => Ok(Mark::Lock(clears)),
+						_ => Err(format!("invalid cell `{cell}`")),
+					},
+					_ => Err(format!("invalid cell `{cell}`")),
+				}
+			})
+			.collect::<Result<Vec<_>, _>>()?;
+		if cells.is_empty() {
+			return Err(String::from("empty row"));
+		}
+		grid.push(cells);
+	}
+	if !grid.is_empty() {
+		grids.push(grid);
+	}
+	Ok(grids)
+}
+
+pub(crate) fn board_from_grid(grid: &Grid) -> Result<Board, String> {
+	let columns = grid[0].len();
+	if grid.iter().any(|row| row.len() != columns) {
+		return Err(String::from("board rows differ in length"));
+	}
+	if columns * grid.len() > BoardSize::MAX_CELLS {
+		return Err(format!("board has more than {} cells", BoardSize::MAX_CELLS));
+	}
+
+	let mut board = Board::new(BoardSize::new(columns, grid.len()));
+	for (row, cells) in grid.iter().enumerate() {
+		for (column, &cell) in cells.iter().enumerate() {
+			let cell = match cell {
+				Mark::Char(FILLED) => Cell::Filled,
+				Mark::Char(EMPTY) => Cell::Empty,
+				Mark::Char(STONE) => Cell::Stone,
+				Mark::Char(ICE) => Cell::Ice,
+				Mark::Char(CRACKED_ICE) => Cell::CrackedIce,
+				Mark::Lock(clears) => Cell::Locked(clears),
+				Mark::Char(_) => return Err(format!("invalid board cell `{cell}`")),
+			};
+			board.set_cell(Coord::new(column, row), cell);
+		}
+	}
+	Ok(board)
+}
+
+pub(crate) fn piece_from_grid(grid: &Grid) -> Result<Piece, String> {
+	let used = grid.iter().flatten().any(|&cell| cell == Mark::Char(USED));
+	let mark = Mark::Char(if used { USED } else { FILLED });
+	if grid.iter().flatten().any(|&cell| cell != mark && cell != Mark::Char(EMPTY)) {
+		return Err(String::from("piece mixes used and unused cells"));
+	}
+
+	let coords = grid
+		.iter()
+		.enumerate()
+		.flat_map(|(row, cells)| {
+			cells.iter().enumerate().filter(|&(_, &cell)| cell == mark).map(move |(column, _)| Coord::new(column, row))
+		})
+		.collect::<Vec<_>>();
+	let shape = Shape::from_coords(&coords).ok_or_else(|| String::from("piece matches no shape"))?;
+	Ok(Piece { shape, used })
+}
+
+/// Writes a grid of `columns` by `rows` cells, asking `cell` for each.
+fn write_grid(f: &mut fmt::Formatter<'_>, columns: usize, rows: usize, cell: impl Fn(Coord) -> Mark) -> fmt::Result {
+	for row in 0..rows {
+		let cells = (0..columns).map(|column| cell(Coord::new(column, row)).to_string()).collect::<Vec<_>>();
+		writeln!(f, "[{}]", cells.join(" "))?;
+	}
+	Ok(())
+}
+
+/// One `[...]` line per row, `x` for filled and `.` for empty cells, and the marks of obstacles.
+impl fmt::Display for Board {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let size = self.size();
+		write_grid(f, size.columns, size.rows, |coord| match self.cell(coord) {
+			Cell::Empty => Mark::Char(EMPTY),
+			Cell::Filled => Mark::Char(FILLED),
+			Cell::Stone => Mark::Char(STONE),
+			Cell::Ice => Mark::Char(ICE),
+			Cell::CrackedIce => Mark::Char(CRACKED_ICE),
+			Cell::Locked(clears) => Mark::Lock(clears),
+		})
+	}
+}
+
+/// Parses a board written by [`Display`](fmt::Display); its size follows from the rows.
+impl FromStr for Board {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match grids(s)?.as_slice() {
+			[grid] => board_from_grid(grid),
+			[] => Err(String::from("no board")),
+			_ => Err(String::from("expected a single board")),
+		}
+	}
+}
+
+/// The board followed by the pieces of the tray, in the notation of this module.
+impl fmt::Display for KoalaKombo {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.board())?;
+		for piece in &self.pieces {
+			let coords = piece.shape.get_coords();
+			let columns = coords.iter().map(|c| c.column + 1).max().unwrap_or_default();
+			let rows = coords.iter().map(|c| c.row + 1).max().unwrap_or_default();
+			let mark = if piece.used { USED } else { FILLED };
+			writeln!(f)?;
+			write_grid(f, columns, rows, |coord| Mark::Char(if coords.contains(&coord) { mark } else { EMPTY }))?;
+		}
+		Ok(())
+	}
+}
+
+impl KoalaKombo {
+	/// Builds a game at the position written by [`Display`](fmt::Display), with the default rules.
+	/// The tray may be left out, then it is dealt as usual.
+	pub fn from_ascii(s: &str) -> Result<Self, String> {
+		let grids = grids(s)?;
+		let (board, tray) = grids.split_first().ok_or_else(|| String::from("no board"))?;
+		let mut game = KoalaKombo::builder().with_board(board_from_grid(board)?).build();
+
+		match tray {
+			[] => {},
+			[a, b, c] => {
+				game.pieces = [piece_from_grid(a)?, piece_from_grid(b)?, piece_from_grid(c)?];
+				if game.pieces.iter().all(|piece| piece.used) {
+					return Err(String::from("every piece of the tray is used"));
+				}
+			},
+			tray => return Err(format!("expected a tray of 3 pieces, found {}", tray.len())),
+		}
+		Ok(game)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn from_ascii_test() {
+		let game = KoalaKombo::from_ascii(
+			"
+			[. . . .]
+			[x x . x]
+			[. . . .]
+
+			[x x]
+			[x x]
+
+			[o o o]
+
+			[. x]
+			[x x]
+			[. x]
+			",
+		)
+		.unwrap();
+		assert_eq!(game.size(), BoardSize::new(4, 3));
+		assert!(game.cell_filled(Coord::new(3, 1)));
+		assert!(!game.cell_filled(Coord::new(2, 1)));
+		assert_eq!(
+			game.pieces.map(|p| (p.shape, p.used)),
+			[
+				(Shape::Smashboy, false),
+				(Shape::ShortHero, true),
+				(Shape::TeeweeLeft, false),
+			]
+		);
+
+		// The tray is optional, but must be complete when given
+		assert_eq!(KoalaKombo::from_ascii("[x .]\n[. x]").unwrap().size(), BoardSize::new(2, 2));
+		assert!(KoalaKombo::from_ascii("[x .]\n\n[x]").is_err());
+		assert!(KoalaKombo::from_ascii("[x .]\n[.]").is_err());
+		assert!(KoalaKombo::from_ascii("[x o]").is_err());
+		assert!(KoalaKombo::from_ascii("[x x]\n\n[x . x]\n\n[x]\n\n[x]").is_err());
+		assert!(KoalaKombo::from_ascii("").is_err());
+	}
+
+	#[test]
+	fn round_trip_test() {
+		let mut game = KoalaKombo::builder().with_size(BoardSize::new(6, 5)).with_seed(2).build();
+		game.pieces[1] = Piece {
+			shape: Shape::Single,
+			used: false,
+		};
+		assert!(game.place_shape(1, Coord::new(4, 2)).is_ok());
+
+		let text = game.to_string();
+		assert!(text.starts_with("[. . . . . .]\n[. . . . . .]\n[. . . . x .]\n"));
+		assert!(text.contains("\n[o]\n"));
+		let parsed = KoalaKombo::from_ascii(&text).unwrap();
+		assert_eq!(parsed.board(), game.board());
+		assert_eq!(parsed.pieces.map(|p| (p.shape, p.used)), game.pieces.map(|p| (p.shape, p.used)));
+		assert_eq!(parsed.to_string(), text);
+
+		assert_eq!(game.board().to_string().parse::<Board>().as_ref(), Ok(game.board()));
+	}
+
+	#[test]
+	fn obstacles_test() {
+		let board = "[# * ~]\n[3 x .]".parse::<Board>().unwrap();
+		assert_eq!(board.cell(Coord::new(0, 0)), Cell::Stone);
+		assert_eq!(board.cell(Coord::new(1, 0)), Cell::Ice);
+		assert_eq!(board.cell(Coord::new(2, 0)), Cell::CrackedIce);
+		assert_eq!(board.cell(Coord::new(0, 1)), Cell::Locked(3));
+		assert_eq!(board.cell(Coord::new(2, 1)), Cell::Empty);
+		assert_eq!(board.to_string(), "[# * ~]\n[3 x .]\n");
+
+		// Obstacles only go on boards
+		assert!(KoalaKombo::from_ascii("[. .]\n\n[#]\n\n[x]\n\n[x]").is_err());
+		assert!("[0 .]".parse::<Board>().is_err());
+		assert!("[-1 .]".parse::<Board>().is_err());
+
+		// Locks waiting for many lines take more than one digit
+		let board = "[12 .]".parse::<Board>().unwrap();
+		assert_eq!(board.cell(Coord::new(0, 0)), Cell::Locked(12));
+		assert_eq!(board.to_string(), "[12 .]\n");
+	}
+}
@@ -9,36 +9,156 @@ use fyrox::{
 	},
 	dpi::LogicalSize,
 	engine::{GraphicsContext, GraphicsContextParams, executor::Executor},
+	event::{ElementState, Event, KeyEvent, WindowEvent},
 	event_loop::EventLoop,
 	graph::SceneGraph,
 	gui::{
-		BuildContext, HorizontalAlignment, Thickness, UiNode, UserInterface, VerticalAlignment,
+		BuildContext, HorizontalAlignment, Orientation, Thickness, UiNode, UserInterface, VerticalAlignment,
 		border::BorderBuilder,
 		brush::Brush,
+		button::{ButtonBuilder, ButtonMessage},
+		copypasta::ClipboardProvider,
 		grid::{Column, GridBuilder, Row},
 		message::{MessageDirection, MouseButton, UiMessage},
+		stack_panel::StackPanelBuilder,
 		text::{TextBuilder, TextMessage},
 		widget::{WidgetBuilder, WidgetMessage},
 	},
+	keyboard::{KeyCode, ModifiersState, PhysicalKey},
 	plugin::{Plugin, PluginContext, error::GameResult},
 	window::WindowAttributes,
 };
 
-use crate::koala_kombo::{Coord, GRID_SIZE, KoalaKombo, Piece};
+use crate::{
+	clock::Clock,
+	daily::{self, DailyHistory, DailyResult},
+	date::Date,
+	highscores::{HighScore, HighScores},
+	hint::Hint,
+	koala_kombo::{BoardSize, Cell, Coord, KoalaKombo, Move, Piece},
+	mode::GameMode,
+	puzzle::{Puzzle, PuzzleStatus},
+	replay::Replay,
+	save, storage,
+};
 
 const GAP_PX: f32 = 1.0;
 
+/// Storage key of the puzzle to play next.
+const PUZZLE_KEY: &str = "puzzle";
+
+/// Seconds a replay takes per move at normal speed, half to show the move and half to play it.
+const REPLAY_MOVE_SECONDS: f32 = 1.2;
+
+/// Playback speeds the speed button cycles through.
+const REPLAY_SPEEDS: [f32; 3] = [1.0, 2.0, 4.0];
+
 #[derive(Default, Visit, Reflect, Debug, Clone)]
 pub struct GamePlugin {
-	#[visit(skip)]
 	#[reflect(hidden)]
 	state: Option<KoalaKombo>,
 
+	/// Seed passed on the command line, the first game of this session is dealt from it when set.
+	#[visit(skip)]
+	#[reflect(hidden)]
+	seed: Option<u64>,
+
+	/// Board dimensions passed on the command line, the default 8x8 otherwise.
+	#[visit(skip)]
+	#[reflect(hidden)]
+	size: BoardSize,
+
+	#[visit(skip)]
+	#[reflect(hidden)]
+	mode: GameMode,
+
+	/// Game left unfinished by the last session, kept until the player decides whether to resume it.
+	#[visit(skip)]
+	#[reflect(hidden)]
+	pending_resume: Option<(GameMode, KoalaKombo)>,
+
+	/// Seed and moves of the game as it was last saved, so it isn't written again while nothing was played.
+	#[visit(skip)]
+	#[reflect(hidden)]
+	saved: Option<(u64, Vec<Move>)>,
+
+	#[visit(skip)]
+	#[reflect(hidden)]
+	high_scores: HighScores,
+
+	/// How the current game went into the high scores, so undoing past its end and finishing it
+	/// again updates its entry instead of adding a second one.
+	#[visit(skip)]
+	#[reflect(hidden)]
+	recorded_score: Option<HighScore>,
+
+	/// Placement suggested by the hint button, shown until the board changes.
+	#[visit(skip)]
+	#[reflect(hidden)]
+	hint: Option<Hint>,
+
+	/// Replay being watched; the board and tray show it instead of a game to play.
+	#[visit(skip)]
+	#[reflect(hidden)]
+	replay: Option<ReplayState>,
+
+	/// Replay file passed on the command line, watched as soon as the window is up.
+	#[visit(skip)]
+	#[reflect(hidden)]
+	replay_file: Option<Replay>,
+
+	#[visit(skip)]
+	#[reflect(hidden)]
+	puzzles: Vec<Puzzle>,
+
+	/// Index of the puzzle played in puzzle mode.
+	#[visit(skip)]
+	#[reflect(hidden)]
+	puzzle: usize,
+
+	#[visit(skip)]
+	#[reflect(hidden)]
+	daily: DailyHistory,
+
+	/// Day of the daily challenge being played for score, `None` for practice and other modes.
+	#[visit(skip)]
+	#[reflect(hidden)]
+	daily_date: Option<Date>,
+
 	// UI handles
 	board_cells: Vec<Handle<UiNode>>,
+	/// Text inside each board cell, showing the lines a locked cell still waits for.
+	board_cell_texts: Vec<Handle<UiNode>>,
 	piece_tray: Handle<UiNode>,
 	piece_widgets: Vec<Handle<UiNode>>,
+	hold_slot: Handle<UiNode>,
+	/// Drawing of the held piece inside the hold slot.
+	hold_shape: Handle<UiNode>,
 	score_text: Handle<UiNode>,
+	seed_text: Handle<UiNode>,
+	hint_text: Handle<UiNode>,
+	game_over_overlay: Handle<UiNode>,
+	game_over_heading: Handle<UiNode>,
+	final_score_text: Handle<UiNode>,
+	high_score_text: Handle<UiNode>,
+	restart_button: Handle<UiNode>,
+	next_puzzle_button: Handle<UiNode>,
+	share_button: Handle<UiNode>,
+	resume_overlay: Handle<UiNode>,
+	resume_text: Handle<UiNode>,
+	resume_button: Handle<UiNode>,
+	new_game_button: Handle<UiNode>,
+	undo_button: Handle<UiNode>,
+	hint_button: Handle<UiNode>,
+	watch_replay_button: Handle<UiNode>,
+	replay_bar: Handle<UiNode>,
+	replay_play_button: Handle<UiNode>,
+	replay_play_text: Handle<UiNode>,
+	replay_step_button: Handle<UiNode>,
+	replay_speed_button: Handle<UiNode>,
+	replay_speed_text: Handle<UiNode>,
+	replay_exit_button: Handle<UiNode>,
+	replay_progress_text: Handle<UiNode>,
 
 	// Layout sizes (stored for rebuilding)
 	#[visit(skip)]
@@ -49,25 +169,220 @@ pub struct GamePlugin {
 	#[visit(skip)]
 	#[reflect(hidden)]
 	dragging: Option<DragState>,
+
+	/// Tray piece under the cursor, turned by the rotate key.
+	#[visit(skip)]
+	#[reflect(hidden)]
+	hovered_piece: Option<usize>,
+
+	// Keyboard modifiers, tracked for shortcuts
+	#[visit(skip)]
+	#[reflect(hidden)]
+	modifiers: ModifiersState,
 }
 
 #[derive(Debug, Clone)]
 struct DragState {
 	shape: usize,
 	hover_cell: Option<Coord>,
+	/// The finger dragging the piece, `None` for the mouse.
+	touch: Option<u64>,
+	/// Whether the piece is over the hold slot, to be held when dropped.
+	over_hold: bool,
+}
+
+#[derive(Debug, Clone)]
+struct ReplayState {
+	replay: Replay,
+	/// Index of the next move to play.
+	next: usize,
+	playing: bool,
+	/// Index into [`REPLAY_SPEEDS`].
+	speed: usize,
+	/// Seconds left until the next step while playing.
+	timer: f32,
+	/// Whether the next move is shown on the board, waiting to be played.
+	previewing: bool,
+}
+
+impl ReplayState {
+	fn new(replay: Replay) -> Self {
+		Self {
+			replay,
+			next: 0,
+			playing: true,
+			speed: 0,
+			timer: 0.0,
+			previewing: false,
+		}
+	}
+
+	/// The move about to be played, `None` once the replay is over.
+	fn next_move(&self) -> Option<Move> {
+		self.replay.moves.get(self.next).copied()
+	}
 }
 
 impl GamePlugin {
-	fn start_drag(&mut self, piece_idx: usize, pos: &Vector2<f32>, ui: &mut UserInterface) {
+	/// Builds the next game of the mode. A seed from the command line only deals the first one,
+	/// so restarting still brings a new game.
+	fn new_game(&mut self) -> KoalaKombo {
+		if let Some(puzzle) = self.puzzle() {
+			return puzzle.game();
+		}
+		if self.mode == GameMode::Daily {
+			return daily::game(Date::today());
+		}
+
+		let builder = self.mode.builder().with_size(self.size);
+		match self.seed.take() {
+			Some(seed) => builder.with_seed(seed),
+			None => builder,
+		}
+		.build()
+	}
+
+	/// The puzzle being played, `None` outside of puzzle mode.
+	fn puzzle(&self) -> Option<&Puzzle> {
+		self.puzzles.get(self.puzzle).filter(|_| self.mode == GameMode::Puzzle)
+	}
+
+	/// Decides if the new game is the scored attempt at today's daily challenge, the first game of the day.
+	fn start_daily_attempt(&mut self) {
+		let today = Date::today();
+		self.daily_date = (self.mode == GameMode::Daily && self.daily.result(today).is_none()).then_some(today);
+	}
+
+	/// Keeps the score of the daily attempt after every move, so leaving the game ends the attempt.
+	fn record_daily(&mut self) {
+		let Some(date) = self.daily_date else {
+			return;
+		};
+		let score = self.state.as_ref().unwrap().score;
+		self.daily.record(DailyResult { date, score });
+		if let Err(err) = self.daily.save() {
+			Log::err(format!("Failed to save the daily results: {err}"));
+		}
+	}
+
+	/// Copies the result of the daily attempt to the clipboard, to be pasted into a chat.
+	fn share_daily(&self, ui: &UserInterface) {
+		let Some(date) = self.daily_date else {
+			return;
+		};
+		let text = daily::share(date, self.state.as_ref().unwrap());
+		let copied = ui.clipboard_mut().is_some_and(|mut clipboard| clipboard.set_contents(text).is_ok());
+		let message = if copied {
+			"Result copied to the clipboard"
+		} else {
+			"The clipboard is not available"
+		};
+		ui.send_message(UiMessage::for_widget(self.high_score_text, TextMessage::Text(message.to_string())));
+		ui.send_message(UiMessage::for_widget(self.high_score_text, WidgetMessage::Visibility(true)));
+	}
+
+	/// Runs the clock of a blitz game down, ending the game when it runs out.
+	fn tick_clock(&mut self, dt: f32, ui: &mut UserInterface) {
+		// Replays show the moves, not the time they took
+		if self.replay.is_some() {
+			return;
+		}
+		let Some(clock) = self.state.as_mut().filter(|state| !state.is_game_over()).and_then(KoalaKombo::clock_mut) else {
+			return;
+		};
+
+		let shown = clock.seconds_left();
+		let expired = clock.tick(dt);
+		let changed = clock.seconds_left() != shown;
+		if expired {
+			// The piece in hand goes back to the tray
+			if let Some(drag) = &mut self.dragging {
+				drag.hover_cell = None;
+			}
+			self.end_drag(ui);
+			self.refresh(ui);
+			self.show_game_over(ui);
+		} else if changed {
+			ui.send_message(UiMessage::for_widget(self.seed_text, TextMessage::Text(self.info_text())));
+		}
+	}
+
+	fn set_clock_paused(&mut self, paused: bool, ui: &UserInterface) {
+		let Some(clock) = self.state.as_mut().and_then(KoalaKombo::clock_mut) else {
+			return;
+		};
+		if paused {
+			clock.pause();
+		} else {
+			clock.resume();
+		}
+		ui.send_message(UiMessage::for_widget(self.seed_text, TextMessage::Text(self.info_text())));
+	}
+
+	/// Turns a tray piece to its next orientation, in the tray or in hand, if the mode allows it.
+	fn rotate_piece(&mut self, piece_idx: usize, ui: &mut UserInterface) {
+		if self.replay.is_some() || !self.state.as_mut().unwrap().rotate_piece(piece_idx) {
+			return;
+		}
+		self.hint = None;
+
+		// Only the drawing changes, the widget stays where it is
+		let widget = self.piece_widgets[piece_idx];
+		if let Some(&old) = ui.node(widget).children().first() {
+			ui.send_message(UiMessage::for_widget(old, WidgetMessage::Remove));
+		}
+		let piece = self.state.as_ref().unwrap().pieces[piece_idx];
+		let shape = Self::build_piece_shape(&mut ui.build_ctx(), &piece);
+		ui.send_message(UiMessage::for_widget(shape, WidgetMessage::LinkWith(widget)));
+
+		self.refresh(ui);
+	}
+
+	/// Swaps a tray piece with the hold slot, or takes the held piece back into the empty slot.
+	fn hold_piece(&mut self, piece_idx: usize, ui: &mut UserInterface) {
+		if self.replay.is_some() {
+			return;
+		}
+		let held = self.state.as_mut().unwrap().hold_piece(piece_idx);
+		self.hint = None;
+
+		// Also puts a piece dropped on the slot back into the tray when it can't be held
+		self.rebuild_piece_tray(ui);
+		self.refresh(ui);
+		if held && self.is_finished() {
+			self.show_game_over(ui);
+		}
+	}
+
+	/// Takes the held piece back into the first empty slot of the tray, if there is one.
+	fn take_held_piece(&mut self, ui: &mut UserInterface) {
+		if let Some(piece_idx) = self.state.as_ref().unwrap().pieces.iter().position(|piece| piece.used) {
+			self.hold_piece(piece_idx, ui);
+		}
+	}
+
+	/// Returns if the game has ended: no more moves in endless play, solved or failed in puzzle mode.
+	fn is_finished(&self) -> bool {
 		let state = self.state.as_ref().unwrap();
-		if state.pieces[piece_idx].used {
+		match self.puzzle() {
+			Some(puzzle) => puzzle.status(state) != PuzzleStatus::Playing,
+			None => state.is_game_over(),
+		}
+	}
+
+	fn start_drag(&mut self, piece_idx: usize, pos: &Vector2<f32>, touch: Option<u64>, ui: &mut UserInterface) {
+		let state = self.state.as_ref().unwrap();
+		if state.pieces[piece_idx].used || self.replay.is_some() {
 			return;
 		}
 
 		self.dragging = Some(DragState {
 			shape: piece_idx,
 			hover_cell: None,
+			touch,
+			over_hold: false,
 		});
+		self.hint = None;
 
 		let widget = self.piece_widgets[piece_idx];
 
@@ -87,61 +402,430 @@ impl GamePlugin {
 	}
 
 	fn update_drag(&mut self, pos: &Vector2<f32>, ui: &UserInterface) {
-		if let Some(ref drag) = self.dragging {
+		let can_hold = self.state.as_ref().unwrap().can_hold();
+		if let Some(ref mut drag) = self.dragging {
 			let widget = self.piece_widgets[drag.shape];
 			let half_size = (self.piece_widget_size - 8.0) / 2.0;
 			let offset = *pos - Vector2::new(half_size, half_size);
 			ui.send_message(UiMessage::for_widget(widget, WidgetMessage::DesiredPosition(offset)));
+
+			let over_hold = can_hold && ui.node(self.hold_slot).screen_bounds().contains(*pos);
+			if over_hold != drag.over_hold {
+				drag.over_hold = over_hold;
+				self.refresh(ui);
+			}
 		}
 	}
 
 	fn update_hover_from_pos(&mut self, pos: &Vector2<f32>, ui: &UserInterface) {
 		let new_cell = self.find_board_cell_at_pos(pos, ui);
-		if let Some(ref mut drag) = self.dragging {
-			if new_cell != drag.hover_cell {
-				drag.hover_cell = new_cell;
-				self.refresh(ui);
-			}
+		if let Some(ref mut drag) = self.dragging
+			&& new_cell != drag.hover_cell
+		{
+			drag.hover_cell = new_cell;
+			self.refresh(ui);
 		}
 	}
 
 	fn end_drag(&mut self, ui: &mut UserInterface) {
 		if let Some(drag) = self.dragging.take() {
+			if drag.over_hold {
+				self.hold_piece(drag.shape, ui);
+				return;
+			}
 			let state = self.state.as_mut().unwrap();
 
-			let placed = if let Some(hover) = drag.hover_cell {
-				state.place_shape(drag.shape, hover)
-			} else {
-				false
-			};
+			let placed = drag.hover_cell.and_then(|hover| state.place_shape(drag.shape, hover).ok());
+			if placed.is_some() {
+				self.record_daily();
+			}
 
-			if placed {
-				if state.pieces.iter().all(|p| !p.used) {
+			match placed {
+				Some(ref outcome) if outcome.refilled => self.rebuild_piece_tray(ui),
+				Some(_) => self.update_piece_visibility(ui),
+				None => {
 					self.rebuild_piece_tray(ui);
-				} else {
 					self.update_piece_visibility(ui);
-				}
-			} else {
-				self.rebuild_piece_tray(ui);
-				self.update_piece_visibility(ui);
+				},
 			}
 
 			self.refresh(ui);
+
+			if placed.is_some() && self.is_finished() {
+				self.show_game_over(ui);
+			}
+		}
+	}
+
+	fn show_game_over(&mut self, ui: &UserInterface) {
+		if self.puzzle().is_some() {
+			self.show_puzzle_result(ui);
+			return;
+		}
+
+		// A finished game is not worth resuming
+		save::delete_save();
+		self.saved = None;
+
+		self.save_replay();
+
+		let state = self.state.as_ref().unwrap();
+
+		// Practice games of the daily challenge don't count
+		let practice = self.mode == GameMode::Daily && self.daily_date.is_none();
+		let entry = HighScore {
+			mode: self.mode,
+			date: Date::today(),
+			score: state.score,
+			seed: state.seed(),
+		};
+		// A game that ends again after undoing only counts with its better ending
+		let rank = if practice || self.recorded_score.as_ref().is_some_and(|old| old.score >= entry.score) {
+			None
+		} else {
+			let rank = match self.recorded_score.replace(entry.clone()) {
+				Some(old) => self.high_scores.replace(&old, entry),
+				None => self.high_scores.record(entry),
+			};
+			if let Err(err) = self.high_scores.save() {
+				Log::err(format!("Failed to save the high scores: {err}"));
+			}
+			rank
+		};
+		let ranking = match rank {
+			Some(0) => String::from("New high score!"),
+			Some(rank) => format!("#{} of the {} high scores", rank + 1, self.mode),
+			None if practice => match self.daily.result(Date::today()) {
+				Some(result) => format!("Practice game, today's score is {}", result.score),
+				None => String::from("Practice game"),
+			},
+			None => String::new(),
+		};
+		ui.send_message(UiMessage::for_widget(self.high_score_text, WidgetMessage::Visibility(rank.is_some() || practice)));
+		ui.send_message(UiMessage::for_widget(self.high_score_text, TextMessage::Text(ranking)));
+
+		ui.send_message(UiMessage::for_widget(
+			self.final_score_text,
+			TextMessage::Text(format!("Final score: {}\nSeed: {}", state.score, state.seed())),
+		));
+		let heading = if state.clock().is_some_and(Clock::is_expired) {
+			"Time's up!"
+		} else {
+			"Game Over"
+		};
+		ui.send_message(UiMessage::for_widget(self.game_over_heading, TextMessage::Text(heading.to_string())));
+		ui.send_message(UiMessage::for_widget(self.next_puzzle_button, WidgetMessage::Visibility(false)));
+		ui.send_message(UiMessage::for_widget(self.share_button, WidgetMessage::Visibility(self.daily_date.is_some())));
+		ui.send_message(UiMessage::for_widget(self.watch_replay_button, WidgetMessage::Visibility(true)));
+		ui.send_message(UiMessage::for_widget(self.game_over_overlay, WidgetMessage::Visibility(true)));
+	}
+
+	/// Tells whether the puzzle was solved, offering the next one if so and another try otherwise.
+	fn show_puzzle_result(&self, ui: &UserInterface) {
+		let puzzle = self.puzzle().unwrap();
+		let solved = puzzle.status(self.state.as_ref().unwrap()) == PuzzleStatus::Solved;
+		if solved {
+			let next = (self.puzzle + 1) % self.puzzles.len();
+			if let Err(err) = storage::save(PUZZLE_KEY, &next.to_string()) {
+				Log::err(format!("Failed to save the puzzle progress: {err}"));
+			}
+		}
+
+		let heading = if solved { "Solved!" } else { "Not quite" };
+		ui.send_message(UiMessage::for_widget(self.game_over_heading, TextMessage::Text(heading.to_string())));
+		ui.send_message(UiMessage::for_widget(
+			self.final_score_text,
+			TextMessage::Text(format!("{}\n{}", puzzle.name, puzzle.objective())),
+		));
+		ui.send_message(UiMessage::for_widget(self.high_score_text, WidgetMessage::Visibility(false)));
+		ui.send_message(UiMessage::for_widget(self.next_puzzle_button, WidgetMessage::Visibility(solved)));
+		ui.send_message(UiMessage::for_widget(self.share_button, WidgetMessage::Visibility(false)));
+		ui.send_message(UiMessage::for_widget(self.watch_replay_button, WidgetMessage::Visibility(false)));
+		ui.send_message(UiMessage::for_widget(self.game_over_overlay, WidgetMessage::Visibility(true)));
+	}
+
+	fn next_puzzle(&mut self, ui: &mut UserInterface) {
+		self.puzzle = (self.puzzle + 1) % self.puzzles.len();
+		self.restart(ui);
+	}
+
+	/// Records the game on the board as the replay of the last game, when it ends and also when it is left unfinished.
+	/// Puzzles can't be replayed, and a replay being watched or a game without moves is nothing to record.
+	fn save_replay(&self) {
+		if self.replay.is_some() || self.puzzle().is_some() {
+			return;
+		}
+		let Some(state) = self.state.as_ref().filter(|state| !state.moves().is_empty()) else {
+			return;
+		};
+		if let Err(err) = Replay::from_game(self.mode, state).save() {
+			Log::err(format!("Failed to save the replay: {err}"));
+		}
+	}
+
+	/// Starts a fresh game in place, rebuilding the board and tray without recreating the UI.
+	fn restart(&mut self, ui: &mut UserInterface) {
+		self.state = Some(self.new_game());
+		self.dragging = None;
+		self.hovered_piece = None;
+		self.hint = None;
+		self.recorded_score = None;
+		self.start_daily_attempt();
+
+		self.rebuild_piece_tray(ui);
+		ui.send_message(UiMessage::for_widget(self.game_over_overlay, WidgetMessage::Visibility(false)));
+		self.refresh(ui);
+	}
+
+	/// Offers to resume the game saved by the last session, if it fits the board of this one.
+	fn offer_resume(&mut self, ui: &UserInterface) {
+		// Another mode was picked on purpose, the endless game can wait for the next launch
+		if !self.mode.can_resume() {
+			return;
+		}
+		let Some((mode, game)) = save::load_game() else {
+			return;
+		};
+		if game.size() != self.size || game.is_game_over() {
+			return;
+		}
+
+		ui.send_message(UiMessage::for_widget(
+			self.resume_text,
+			TextMessage::Text(format!("Score: {}\nMode: {}\nSeed: {}", game.score, mode, game.seed())),
+		));
+		ui.send_message(UiMessage::for_widget(self.resume_overlay, WidgetMessage::Visibility(true)));
+		self.pending_resume = Some((mode, game));
+	}
+
+	/// Answers the resume prompt, either continuing the saved game or dropping it for the fresh one.
+	fn resume(&mut self, resume: bool, ui: &mut UserInterface) {
+		let Some((mode, game)) = self.pending_resume.take() else {
+			return;
+		};
+		ui.send_message(UiMessage::for_widget(self.resume_overlay, WidgetMessage::Visibility(false)));
+
+		if resume {
+			self.mode = mode;
+			self.state = Some(game);
+			self.recorded_score = None;
+			self.sync_after_history(ui);
+		} else {
+			save::delete_save();
+		}
+	}
+
+	/// Saves the game in progress so the next launch can offer to resume it.
+	fn autosave(&mut self) {
+		// The save of the last session stays untouched until the player has answered the prompt,
+		// and a replay is nothing to resume
+		if self.pending_resume.is_some() || self.replay.is_some() || !self.mode.can_resume() {
+			return;
+		}
+		let Some(state) = self.state.as_mut() else {
+			return;
+		};
+
+		// A finished game is nothing to resume, and neither is one undone back to its start
+		if state.is_game_over() || state.moves().is_empty() {
+			save::delete_save();
+			self.saved = None;
+			return;
+		}
+		let saved = (state.seed(), state.moves().to_vec());
+		if self.saved.as_ref() == Some(&saved) {
+			return;
+		}
+		match save::save_game(self.mode, state) {
+			Ok(()) => self.saved = Some(saved),
+			Err(err) => Log::err(format!("Failed to save the game: {err:?}")),
+		}
+	}
+
+	fn undo(&mut self, ui: &mut UserInterface) {
+		if self.dragging.is_none() && self.replay.is_none() && self.state.as_mut().unwrap().undo() {
+			self.sync_after_history(ui);
+		}
+	}
+
+	fn redo(&mut self, ui: &mut UserInterface) {
+		if self.dragging.is_none() && self.replay.is_none() && self.state.as_mut().unwrap().redo() {
+			self.sync_after_history(ui);
+		}
+	}
+
+	fn show_hint(&mut self, ui: &UserInterface) {
+		if self.dragging.is_none() && self.replay.is_none() {
+			self.hint = self.state.as_ref().unwrap().hint();
+			self.refresh(ui);
+		}
+	}
+
+	/// Watches the replay from its first move, replacing the game on the board.
+	fn start_replay(&mut self, replay: Replay, ui: &mut UserInterface) {
+		// The board widgets are built for the size of this session
+		if replay.size != self.size {
+			Log::err(format!("Can't show a replay of a {} board on a {} board", replay.size, self.size));
+			return;
+		}
+
+		self.mode = replay.mode;
+		self.state = Some(replay.game());
+		self.dragging = None;
+		self.hint = None;
+		self.replay = Some(ReplayState::new(replay));
+
+		for widget in [self.undo_button, self.hint_button] {
+			ui.send_message(UiMessage::for_widget(widget, WidgetMessage::Visibility(false)));
+		}
+		ui.send_message(UiMessage::for_widget(self.replay_bar, WidgetMessage::Visibility(true)));
+		ui.send_message(UiMessage::for_widget(self.game_over_overlay, WidgetMessage::Visibility(false)));
+		self.rebuild_piece_tray(ui);
+		self.update_replay_bar(ui);
+		self.refresh(ui);
+	}
+
+	/// Leaves the replay for a fresh game.
+	fn stop_replay(&mut self, ui: &mut UserInterface) {
+		self.replay = None;
+		for widget in [self.undo_button, self.hint_button] {
+			ui.send_message(UiMessage::for_widget(widget, WidgetMessage::Visibility(true)));
+		}
+		ui.send_message(UiMessage::for_widget(self.replay_bar, WidgetMessage::Visibility(false)));
+		self.restart(ui);
+	}
+
+	/// Advances the replay by half a move: shows the next move on the board, or plays the one shown.
+	fn advance_replay(&mut self, ui: &mut UserInterface) {
+		let Some(replay) = self.replay.as_mut() else {
+			return;
+		};
+		let Some(mv) = replay.next_move() else {
+			replay.playing = false;
+			self.update_replay_bar(ui);
+			return;
+		};
+
+		if !replay.previewing {
+			replay.previewing = true;
+			self.refresh(ui);
+			return;
+		}
+
+		replay.previewing = false;
+		replay.next += 1;
+		let placed = self.state.as_mut().unwrap().play_move(mv);
+		// A replay of other rules or a damaged file, stop where it went wrong
+		if let Err(ref err) = placed {
+			Log::err(format!("Move {} of the replay can't be played: {err}", replay.next));
+			replay.next = replay.replay.moves.len();
+		}
+		replay.playing &= replay.next_move().is_some();
+
+		match placed {
+			Ok(Some(outcome)) if !outcome.refilled && mv.turns == 0 => self.update_piece_visibility(ui),
+			// Turning and holding change the pieces themselves
+			_ => self.rebuild_piece_tray(ui),
+		}
+		self.update_replay_bar(ui);
+		self.refresh(ui);
+	}
+
+	/// Plays the next move right away, pausing the replay.
+	fn step_replay(&mut self, ui: &mut UserInterface) {
+		let Some(replay) = self.replay.as_mut() else {
+			return;
+		};
+		replay.playing = false;
+		if !replay.previewing {
+			self.advance_replay(ui);
+		}
+		self.advance_replay(ui);
+	}
+
+	/// Plays or pauses the replay. Playing a finished replay starts it over.
+	fn toggle_replay(&mut self, ui: &mut UserInterface) {
+		let Some(replay) = self.replay.as_mut() else {
+			return;
+		};
+		let finished = replay.next_move().is_none();
+		if finished {
+			self.state = Some(replay.replay.game());
+			replay.next = 0;
+			replay.previewing = false;
+			replay.playing = true;
+		} else {
+			replay.playing = !replay.playing;
 		}
+		replay.timer = 0.0;
+
+		if finished {
+			self.rebuild_piece_tray(ui);
+		}
+		self.update_replay_bar(ui);
+		self.refresh(ui);
+	}
+
+	fn cycle_replay_speed(&mut self, ui: &UserInterface) {
+		if let Some(replay) = self.replay.as_mut() {
+			replay.speed = (replay.speed + 1) % REPLAY_SPEEDS.len();
+			self.update_replay_bar(ui);
+		}
+	}
+
+	fn update_replay_bar(&self, ui: &UserInterface) {
+		let Some(replay) = self.replay.as_ref() else {
+			return;
+		};
+		let play = if replay.playing { "Pause" } else { "Play" };
+		ui.send_message(UiMessage::for_widget(self.replay_play_text, TextMessage::Text(play.to_string())));
+		let speed = format!("{}x", REPLAY_SPEEDS[replay.speed]);
+		ui.send_message(UiMessage::for_widget(self.replay_speed_text, TextMessage::Text(speed)));
+		let progress = format!("Move {}/{}", replay.next, replay.replay.moves.len());
+		ui.send_message(UiMessage::for_widget(self.replay_progress_text, TextMessage::Text(progress)));
+		ui.send_message(UiMessage::for_widget(
+			self.replay_step_button,
+			WidgetMessage::Enabled(replay.next_move().is_some()),
+		));
+	}
+
+	/// Placement to point out on the board and in the tray: the next move of a replay, or the hint.
+	fn highlight(&self) -> Option<(usize, Vec<Coord>)> {
+		if let Some(ref replay) = self.replay {
+			let mv = replay.next_move().filter(|mv| replay.previewing && !mv.hold)?;
+			// Show the piece turned the way it was placed
+			let mut game = self.state.as_ref()?.clone();
+			for _ in 0..mv.turns {
+				game.rotate_piece(mv.piece_idx);
+			}
+			return Some((mv.piece_idx, game.can_place(mv.piece_idx, mv.coord)?));
+		}
+		self.hint.as_ref().map(|hint| (hint.piece_idx, hint.cells.clone()))
+	}
+
+	/// Brings tray and overlay in line with a game state restored from history.
+	fn sync_after_history(&mut self, ui: &mut UserInterface) {
+		self.hint = None;
+		self.rebuild_piece_tray(ui);
+		self.update_piece_visibility(ui);
+
+		let finished = self.is_finished();
+		ui.send_message(UiMessage::for_widget(self.game_over_overlay, WidgetMessage::Visibility(finished)));
+		self.refresh(ui);
 	}
 
 	fn find_board_cell_at_pos(&self, pos: &Vector2<f32>, ui: &UserInterface) -> Option<Coord> {
 		for (idx, &cell_handle) in self.board_cells.iter().enumerate() {
 			let cell = ui.node(cell_handle);
 			if cell.screen_bounds().contains(*pos) {
-				return Some(Coord::from_index(idx));
+				return Some(Coord::from_index(idx, self.size));
 			}
 		}
 		None
 	}
 
 	fn build_ui(&mut self, ctx: &mut BuildContext, screen_size: (f32, f32)) -> Handle<UiNode> {
-		self.state = Some(KoalaKombo::new());
+		self.state = Some(self.new_game());
 
 		let (width, height) = screen_size;
 		let margin = 10.0;
@@ -153,10 +837,13 @@ impl GamePlugin {
 
 		// Board takes remaining height after title, score, and piece tray
 		let title_height = 120.0;
-		let score_height = 80.0;
+		let score_height = 140.0;
 		let header_height = title_height + score_height;
 		let available_for_board = height - header_height - piece_tray_height - margin * 4.0;
-		let board_size = available_for_board.min(width - margin * 2.0); // Keep square, fit in width
+		// Keep cells square and fit the board in both directions
+		let cell_size =
+			(available_for_board / self.size.rows as f32).min((width - margin * 2.0) / self.size.columns as f32);
+		let board_height = cell_size * self.size.rows as f32;
 
 		// Title
 		let title = TextBuilder::new(
@@ -169,20 +856,65 @@ impl GamePlugin {
 		.with_text("Koala Kombo")
 		.build(ctx);
 
+		self.undo_button = ButtonBuilder::new(
+			WidgetBuilder::new()
+				.on_row(0)
+				.with_margin(Thickness::uniform(margin))
+				.with_width(120.0)
+				.with_height(60.0)
+				.with_enabled(false)
+				.with_horizontal_alignment(HorizontalAlignment::Right)
+				.with_vertical_alignment(VerticalAlignment::Center),
+		)
+		.with_text("Undo")
+		.build(ctx)
+		.transmute();
+
+		self.hint_button = ButtonBuilder::new(
+			WidgetBuilder::new()
+				.on_row(0)
+				.with_margin(Thickness::uniform(margin))
+				.with_width(120.0)
+				.with_height(60.0)
+				.with_horizontal_alignment(HorizontalAlignment::Left)
+				.with_vertical_alignment(VerticalAlignment::Center),
+		)
+		.with_text("Hint")
+		.build(ctx)
+		.transmute();
+
+		let replay_bar = self.build_replay_bar(ctx);
+
 		// Score
 		self.score_text = TextBuilder::new(
-			WidgetBuilder::new()
-				.on_row(1)
-				.with_margin(Thickness::uniform(8.0))
-				.with_horizontal_alignment(HorizontalAlignment::Center),
+			WidgetBuilder::new().with_margin(Thickness::uniform(8.0)).with_horizontal_alignment(HorizontalAlignment::Center),
 		)
 		.with_text("Score: 0")
 		.with_font_size(48.0f32.into())
 		.build(ctx)
 		.transmute();
 
+		// Seed of the current run, so it can be quoted in bug reports
+		self.seed_text = TextBuilder::new(WidgetBuilder::new().with_horizontal_alignment(HorizontalAlignment::Center))
+			.with_font_size(24.0f32.into())
+			.build(ctx)
+			.transmute();
+
+		// Reasoning behind the current hint, empty while none is shown
+		self.hint_text = TextBuilder::new(WidgetBuilder::new().with_horizontal_alignment(HorizontalAlignment::Center))
+			.with_font_size(24.0f32.into())
+			.build(ctx)
+			.transmute();
+
+		let score_panel = StackPanelBuilder::new(WidgetBuilder::new().on_row(1).with_children([
+			self.score_text,
+			self.seed_text,
+			self.hint_text,
+		]))
+		.build(ctx);
+
 		// Board grid
-		let board_grid = self.build_board(ctx, board_size);
+		let board_grid = self.build_board(ctx, cell_size);
 		let board_border = BorderBuilder::new(
 			WidgetBuilder::new()
 				.on_row(2)
@@ -193,13 +925,19 @@ impl GamePlugin {
 		.with_stroke_thickness(Thickness::uniform(2.0).into())
 		.build(ctx);
 
-		// Piece tray
-		let piece_children = self.build_piece_widgets(ctx, piece_widget_size);
+		// Piece tray, with the hold slot in a last column that collapses while it is hidden
+		let mut piece_children = self.build_piece_widgets(ctx, piece_widget_size);
+		piece_children.push(self.build_hold_slot(ctx, piece_widget_size));
 		self.piece_tray = GridBuilder::new(
 			WidgetBuilder::new().with_horizontal_alignment(HorizontalAlignment::Center).with_children(piece_children),
 		)
 		.add_rows(vec![Row::strict(piece_widget_size)])
-		.add_columns(vec![Column::strict(piece_widget_size); 3])
+		.add_columns(vec![
+			Column::strict(piece_widget_size),
+			Column::strict(piece_widget_size),
+			Column::strict(piece_widget_size),
+			Column::auto(),
+		])
 		.build(ctx)
 		.transmute();
 
@@ -214,44 +952,285 @@ impl GamePlugin {
 		.build(ctx);
 
 		// Main layout grid
-		GridBuilder::new(WidgetBuilder::new().with_width(width).with_height(height).with_children([
+		let layout = GridBuilder::new(WidgetBuilder::new().with_children([
 			title.transmute(),
-			self.score_text,
+			self.undo_button,
+			self.hint_button,
+			replay_bar,
+			score_panel.transmute(),
 			board_border.transmute(),
 			piece_border.transmute(),
 		]))
 		.add_rows(vec![
-			Row::strict(title_height),              // Title
-			Row::strict(score_height),              // Score
-			Row::strict(board_size + margin * 2.0), // Board + margins
-			Row::strict(piece_tray_height),         // Piece tray
+			Row::strict(title_height),                // Title
+			Row::strict(score_height),                // Score
+			Row::strict(board_height + margin * 2.0), // Board + margins
+			Row::strict(piece_tray_height),           // Piece tray
 		])
 		.add_columns(vec![Column::stretch()])
+		.build(ctx);
+
+		// Overlays share the single root cell with the layout so they cover it entirely
+		let game_over_overlay = self.build_game_over_overlay(ctx);
+		let resume_overlay = self.build_resume_overlay(ctx);
+		GridBuilder::new(WidgetBuilder::new().with_width(width).with_height(height).with_children([
+			layout.transmute(),
+			game_over_overlay,
+			resume_overlay,
+		]))
+		.add_rows(vec![Row::stretch()])
+		.add_columns(vec![Column::stretch()])
 		.build(ctx)
 		.transmute()
 	}
 
-	fn build_board(&mut self, ctx: &mut BuildContext, board_size: f32) -> Handle<UiNode> {
+	fn build_game_over_overlay(&mut self, ctx: &mut BuildContext) -> Handle<UiNode> {
+		self.game_over_heading = TextBuilder::new(
+			WidgetBuilder::new().with_margin(Thickness::uniform(8.0)).with_horizontal_alignment(HorizontalAlignment::Center),
+		)
+		.with_font_size(80.0f32.into())
+		.with_text("Game Over")
+		.build(ctx)
+		.transmute();
+
+		self.final_score_text = TextBuilder::new(
+			WidgetBuilder::new().with_margin(Thickness::uniform(8.0)).with_horizontal_alignment(HorizontalAlignment::Center),
+		)
+		.with_font_size(48.0f32.into())
+		.with_text("Final score: 0")
+		.build(ctx)
+		.transmute();
+
+		self.high_score_text = TextBuilder::new(
+			WidgetBuilder::new()
+				.with_margin(Thickness::uniform(8.0))
+				.with_visibility(false)
+				.with_foreground(Brush::Solid(Color::opaque(255, 215, 0)).into())
+				.with_horizontal_alignment(HorizontalAlignment::Center),
+		)
+		.with_font_size(48.0f32.into())
+		.build(ctx)
+		.transmute();
+
+		self.restart_button = ButtonBuilder::new(
+			WidgetBuilder::new()
+				.with_margin(Thickness::uniform(16.0))
+				.with_width(240.0)
+				.with_height(80.0)
+				.with_horizontal_alignment(HorizontalAlignment::Center),
+		)
+		.with_text("Restart")
+		.build(ctx)
+		.transmute();
+
+		self.next_puzzle_button = ButtonBuilder::new(
+			WidgetBuilder::new()
+				.with_margin(Thickness::uniform(16.0))
+				.with_width(240.0)
+				.with_height(80.0)
+				.with_visibility(false)
+				.with_horizontal_alignment(HorizontalAlignment::Center),
+		)
+		.with_text("Next puzzle")
+		.build(ctx)
+		.transmute();
+
+		self.share_button = ButtonBuilder::new(
+			WidgetBuilder::new()
+				.with_margin(Thickness::uniform(16.0))
+				.with_width(240.0)
+				.with_height(80.0)
+				.with_visibility(false)
+				.with_horizontal_alignment(HorizontalAlignment::Center),
+		)
+		.with_text("Share result")
+		.build(ctx)
+		.transmute();
+
+		self.watch_replay_button = ButtonBuilder::new(
+			WidgetBuilder::new()
+				.with_margin(Thickness::uniform(16.0))
+				.with_width(240.0)
+				.with_height(80.0)
+				.with_horizontal_alignment(HorizontalAlignment::Center),
+		)
+		.with_text("Watch replay")
+		.build(ctx)
+		.transmute();
+
+		let content = StackPanelBuilder::new(
+			WidgetBuilder::new()
+				.with_horizontal_alignment(HorizontalAlignment::Center)
+				.with_vertical_alignment(VerticalAlignment::Center)
+				.with_children([
+					self.game_over_heading,
+					self.final_score_text,
+					self.high_score_text,
+					self.next_puzzle_button,
+					self.share_button,
+					self.restart_button,
+					self.watch_replay_button,
+				]),
+		)
+		.build(ctx);
+
+		self.game_over_overlay = BorderBuilder::new(
+			WidgetBuilder::new()
+				.with_visibility(false)
+				.with_background(Brush::Solid(Color::from_rgba(0, 0, 0, 200)).into())
+				.with_child(content),
+		)
+		.with_stroke_thickness(Thickness::uniform(0.0).into())
+		.build(ctx)
+		.transmute();
+
+		self.game_over_overlay
+	}
+
+	/// Controls of the replay mode, covering the title row while a replay is shown.
+	fn build_replay_bar(&mut self, ctx: &mut BuildContext) -> Handle<UiNode> {
+		// Buttons keep a handle of their label, so it can change with the replay
+		let button = |ctx: &mut BuildContext, text: &str| {
+			let label = TextBuilder::new(WidgetBuilder::new())
+				.with_text(text)
+				.with_horizontal_text_alignment(HorizontalAlignment::Center)
+				.with_vertical_text_alignment(VerticalAlignment::Center)
+				.build(ctx);
+			let button = ButtonBuilder::new(
+				WidgetBuilder::new().with_margin(Thickness::uniform(8.0)).with_width(120.0).with_height(60.0),
+			)
+			.with_content(label)
+			.build(ctx);
+			(button.transmute(), label.transmute())
+		};
+		(self.replay_play_button, self.replay_play_text) = button(ctx, "Pause");
+		(self.replay_step_button, _) = button(ctx, "Step");
+		(self.replay_speed_button, self.replay_speed_text) = button(ctx, "1x");
+		(self.replay_exit_button, _) = button(ctx, "Exit");
+
+		self.replay_progress_text = TextBuilder::new(
+			WidgetBuilder::new().with_margin(Thickness::uniform(8.0)).with_vertical_alignment(VerticalAlignment::Center),
+		)
+		.with_font_size(32.0f32.into())
+		.build(ctx)
+		.transmute();
+
+		let controls = StackPanelBuilder::new(
+			WidgetBuilder::new()
+				.with_horizontal_alignment(HorizontalAlignment::Center)
+				.with_vertical_alignment(VerticalAlignment::Center)
+				.with_children([
+					self.replay_play_button,
+					self.replay_step_button,
+					self.replay_speed_button,
+					self.replay_exit_button,
+					self.replay_progress_text,
+				]),
+		)
+		.with_orientation(Orientation::Horizontal)
+		.build(ctx);
+
+		self.replay_bar = BorderBuilder::new(
+			WidgetBuilder::new()
+				.on_row(0)
+				.with_visibility(false)
+				.with_background(Brush::Solid(Color::from_rgba(20, 20, 20, 255)).into())
+				.with_child(controls),
+		)
+		.with_stroke_thickness(Thickness::uniform(0.0).into())
+		.build(ctx)
+		.transmute();
+
+		self.replay_bar
+	}
+
+	fn build_resume_overlay(&mut self, ctx: &mut BuildContext) -> Handle<UiNode> {
+		let heading = TextBuilder::new(
+			WidgetBuilder::new().with_margin(Thickness::uniform(8.0)).with_horizontal_alignment(HorizontalAlignment::Center),
+		)
+		.with_font_size(64.0f32.into())
+		.with_text("Resume game?")
+		.build(ctx);
+
+		self.resume_text = TextBuilder::new(
+			WidgetBuilder::new().with_margin(Thickness::uniform(8.0)).with_horizontal_alignment(HorizontalAlignment::Center),
+		)
+		.with_font_size(36.0f32.into())
+		.build(ctx)
+		.transmute();
+
+		let button = |ctx: &mut BuildContext, text: &str| {
+			ButtonBuilder::new(
+				WidgetBuilder::new()
+					.with_margin(Thickness::uniform(16.0))
+					.with_width(240.0)
+					.with_height(80.0)
+					.with_horizontal_alignment(HorizontalAlignment::Center),
+			)
+			.with_text(text)
+			.build(ctx)
+			.transmute()
+		};
+		self.resume_button = button(ctx, "Resume");
+		self.new_game_button = button(ctx, "New game");
+
+		let content = StackPanelBuilder::new(
+			WidgetBuilder::new()
+				.with_horizontal_alignment(HorizontalAlignment::Center)
+				.with_vertical_alignment(VerticalAlignment::Center)
+				.with_children([
+					heading.transmute(),
+					self.resume_text,
+					self.resume_button,
+					self.new_game_button,
+				]),
+		)
+		.build(ctx);
+
+		self.resume_overlay = BorderBuilder::new(
+			WidgetBuilder::new()
+				.with_visibility(false)
+				.with_background(Brush::Solid(Color::from_rgba(0, 0, 0, 200)).into())
+				.with_child(content),
+		)
+		.with_stroke_thickness(Thickness::uniform(0.0).into())
+		.build(ctx)
+		.transmute();
+
+		self.resume_overlay
+	}
+
+	fn build_board(&mut self, ctx: &mut BuildContext, cell_size: f32) -> Handle<UiNode> {
 		self.board_cells.clear();
+		self.board_cell_texts.clear();
 
-		let cell_size = board_size / GRID_SIZE as f32;
-		let rows = (0..GRID_SIZE).map(|_| Row::strict(cell_size)).collect::<Vec<_>>();
-		let columns = (0..GRID_SIZE).map(|_| Column::strict(cell_size)).collect::<Vec<_>>();
+		let rows = (0..self.size.rows).map(|_| Row::strict(cell_size)).collect::<Vec<_>>();
+		let columns = (0..self.size.columns).map(|_| Column::strict(cell_size)).collect::<Vec<_>>();
 
-		let mut children = Vec::with_capacity(GRID_SIZE * GRID_SIZE);
-		for row in 0..GRID_SIZE {
-			for column in 0..GRID_SIZE {
+		let mut children = Vec::with_capacity(self.size.cells());
+		for row in 0..self.size.rows {
+			for column in 0..self.size.columns {
+				let text = TextBuilder::new(
+					WidgetBuilder::new()
+						.with_hit_test_visibility(false)
+						.with_horizontal_alignment(HorizontalAlignment::Center)
+						.with_vertical_alignment(VerticalAlignment::Center),
+				)
+				.with_font_size((cell_size * 0.5).into())
+				.build(ctx);
 				let cell = BorderBuilder::new(
 					WidgetBuilder::new()
 						.on_row(row)
 						.on_column(column)
 						.with_margin(Thickness::uniform(GAP_PX * 0.5))
-						.with_background(Brush::Solid(Color::from_rgba(40, 40, 40, 255)).into()),
+						.with_background(Brush::Solid(Color::from_rgba(40, 40, 40, 255)).into())
+						.with_child(text),
 				)
 				.with_stroke_thickness(Thickness::uniform(1.0).into())
 				.build(ctx);
 
 				self.board_cells.push(cell.transmute());
+				self.board_cell_texts.push(text.transmute());
 				children.push(cell.transmute());
 			}
 		}
@@ -278,6 +1257,8 @@ impl GamePlugin {
 					.with_margin(Thickness::uniform(4.0))
 					.with_width(widget_size - 8.0)
 					.with_height(widget_size - 8.0)
+					// Slots a puzzle left empty are dealt as used
+					.with_visibility(!piece.used)
 					.with_child(shape_grid)
 					.with_background(Brush::Solid(Color::TRANSPARENT).into()),
 			)
@@ -291,6 +1272,35 @@ impl GamePlugin {
 		children
 	}
 
+	fn build_hold_slot(&mut self, ctx: &mut BuildContext, widget_size: f32) -> Handle<UiNode> {
+		let state = self.state.as_ref().unwrap();
+		let label = TextBuilder::new(
+			WidgetBuilder::new()
+				.with_margin(Thickness::uniform(4.0))
+				.with_hit_test_visibility(false)
+				.with_horizontal_alignment(HorizontalAlignment::Center)
+				.with_vertical_alignment(VerticalAlignment::Top),
+		)
+		.with_text("Hold")
+		.with_font_size(20.0f32.into())
+		.build(ctx);
+
+		self.hold_slot = BorderBuilder::new(
+			WidgetBuilder::new()
+				.on_column(3)
+				.with_margin(Thickness::uniform(4.0))
+				.with_width(widget_size - 8.0)
+				.with_height(widget_size - 8.0)
+				.with_visibility(state.can_hold())
+				.with_child(label)
+				.with_background(Brush::Solid(Color::from_rgba(30, 30, 30, 255)).into()),
+		)
+		.with_stroke_thickness(Thickness::uniform(2.0).into())
+		.build(ctx)
+		.transmute();
+		self.hold_slot
+	}
+
 	fn build_piece_shape(ctx: &mut BuildContext, piece: &Piece) -> Handle<UiNode> {
 		let (min_column, max_column, min_row, max_row) = piece.shape.get_coords().iter().fold(
 			(usize::MAX, 0, usize::MAX, 0),
@@ -341,7 +1351,7 @@ impl GamePlugin {
 	fn refresh(&self, ui: &UserInterface) {
 		let state = self.state.as_ref().unwrap();
 
-		// Calculate preview cells if dragging over board
+		// Calculate preview cells if dragging over board, or show the hinted placement
 		let (preview_cells, preview_valid) = if let Some(ref drag) = self.dragging
 			&& let Some(hover) = drag.hover_cell
 		{
@@ -352,13 +1362,25 @@ impl GamePlugin {
 				},
 				None => (vec![], false),
 			}
+		} else if let Some((_, cells)) = self.highlight() {
+			(cells, true)
 		} else {
 			(vec![], false)
 		};
 
-		// Paint board cells
-		for row in 0..GRID_SIZE {
-			for column in 0..GRID_SIZE {
+		// Highlight the hinted piece, or the one a replay plays next, in the tray
+		let highlighted_piece = self.highlight().map(|(piece_idx, _)| piece_idx);
+		for (i, &widget) in self.piece_widgets.iter().enumerate() {
+			let color = match highlighted_piece {
+				Some(piece_idx) if piece_idx == i => Color::from_rgba(100, 200, 100, 80),
+				_ => Color::TRANSPARENT,
+			};
+			ui.send_message(UiMessage::for_widget(widget, WidgetMessage::Background(Brush::Solid(color).into())));
+		}
+
+		// Paint board cells, each obstacle in its own color and locked ones with the lines they wait for
+		for row in 0..self.size.rows {
+			for column in 0..self.size.columns {
 				let pos = Coord::new(column, row);
 				let brush = if preview_cells.contains(&pos) {
 					if preview_valid {
@@ -366,21 +1388,86 @@ impl GamePlugin {
 					} else {
 						Brush::Solid(Color::from_rgba(200, 100, 100, 180))
 					}
-				} else if state.cell_filled(pos) {
-					Brush::Solid(Color::from_rgba(100, 150, 255, 255))
 				} else {
-					Brush::Solid(Color::from_rgba(40, 40, 40, 255))
+					Brush::Solid(match state.board().cell(pos) {
+						Cell::Empty => Color::from_rgba(40, 40, 40, 255),
+						Cell::Filled => Color::from_rgba(100, 150, 255, 255),
+						Cell::Stone => Color::from_rgba(120, 115, 110, 255),
+						Cell::Ice => Color::from_rgba(190, 235, 250, 255),
+						Cell::CrackedIce => Color::from_rgba(120, 180, 205, 255),
+						Cell::Locked(_) => Color::from_rgba(170, 125, 50, 255),
+					})
 				};
 
 				ui.send_message(UiMessage::for_widget(
-					self.board_cells[pos.to_index()],
+					self.board_cells[pos.to_index(self.size)],
 					WidgetMessage::Background(brush.into()),
 				));
+				let clears = match state.board().cell(pos) {
+					Cell::Locked(clears) => clears.to_string(),
+					_ => String::new(),
+				};
+				ui.send_message(UiMessage::for_widget(
+					self.board_cell_texts[pos.to_index(self.size)],
+					TextMessage::Text(clears),
+				));
 			}
 		}
 
-		// Update score
-		ui.send_message(UiMessage::for_widget(self.score_text, TextMessage::Text(format!("Score: {}", state.score))));
+		// Update score, calling out the lines the last placement cleared and the running combo
+		let last_clear = state.last_clear();
+		let best = self.high_scores.best(self.mode).unwrap_or_default().max(state.score);
+		let mut score = match last_clear.lines() {
+			0 => format!("Score: {}  Best: {best}", state.score),
+			1 => format!("Score: {} (+{})  Best: {best}", state.score, last_clear.points),
+			lines => format!("Score: {} (+{} for {} lines)  Best: {best}", state.score, last_clear.points, lines),
+		};
+		if state.combo() > 1 {
+			score.push_str(&format!("  Combo x{}", state.combo()));
+		}
+		ui.send_message(UiMessage::for_widget(self.score_text, TextMessage::Text(score)));
+		ui.send_message(UiMessage::for_widget(self.seed_text, TextMessage::Text(self.info_text())));
+		ui.send_message(UiMessage::for_widget(self.undo_button, WidgetMessage::Enabled(state.can_undo())));
+		let hold_brush = if self.dragging.as_ref().is_some_and(|drag| drag.over_hold) {
+			Brush::Solid(Color::from_rgba(100, 200, 100, 180))
+		} else {
+			Brush::Solid(Color::from_rgba(30, 30, 30, 255))
+		};
+		ui.send_message(UiMessage::for_widget(self.hold_slot, WidgetMessage::Background(hold_brush.into())));
+		let hint = self.hint.as_ref().map(ToString::to_string).unwrap_or_default();
+		ui.send_message(UiMessage::for_widget(self.hint_text, TextMessage::Text(hint)));
+	}
+
+	/// Returns the line under the score: the seed, or what the mode has to tell instead.
+	fn info_text(&self) -> String {
+		let state = self.state.as_ref().unwrap();
+		// A puzzle shows what it asks for instead of the seed, which doesn't matter to it
+		match self.puzzle() {
+			Some(puzzle) => {
+				let mut info =
+					format!("Puzzle {}/{}: {}\n{}", self.puzzle + 1, self.puzzles.len(), puzzle.name, puzzle.objective());
+				match puzzle.moves_left(state) {
+					Some(1) => info.push_str(", 1 move left"),
+					Some(moves) => info.push_str(&format!(", {moves} moves left")),
+					None => {},
+				}
+				info
+			},
+			None if self.mode == GameMode::Daily => match (self.daily_date, self.daily.result(Date::today())) {
+				(None, Some(result)) => format!("Daily challenge, practice  Today's score: {}", result.score),
+				_ => format!("Daily challenge {}", Date::today()),
+			},
+			None => match state.clock() {
+				Some(clock) => {
+					let seconds = clock.seconds_left();
+					let paused = if clock.is_paused() { " (paused)" } else { "" };
+					format!("Blitz  Time left: {}:{:02}{paused}", seconds / 60, seconds % 60)
+				},
+				None if state.can_rotate() => format!("Seed: {}  Rotate: right click, R or a second finger", state.seed()),
+				None if state.can_hold() => format!("Seed: {}  Hold: drop a piece on the slot, or H", state.seed()),
+				None => format!("Seed: {}", state.seed()),
+			},
+		}
 	}
 
 	fn rebuild_piece_tray(&mut self, ui: &mut UserInterface) {
@@ -400,6 +1487,26 @@ impl GamePlugin {
 		for widget in new_widgets {
 			ui.send_message(UiMessage::for_widget(widget, WidgetMessage::LinkWith(self.piece_tray)));
 		}
+
+		self.update_hold_slot(ui);
+	}
+
+	/// Draws the held piece into the hold slot, and hides the slot in modes without one.
+	fn update_hold_slot(&mut self, ui: &mut UserInterface) {
+		let state = self.state.as_ref().unwrap();
+		ui.send_message(UiMessage::for_widget(self.hold_slot, WidgetMessage::Visibility(state.can_hold())));
+		if self.hold_shape.is_some() {
+			ui.send_message(UiMessage::for_widget(self.hold_shape, WidgetMessage::Remove));
+		}
+		self.hold_shape = match state.held() {
+			Some(shape) => {
+				let piece = Piece { shape, used: false };
+				let hold_shape = Self::build_piece_shape(&mut ui.build_ctx(), &piece);
+				ui.send_message(UiMessage::for_widget(hold_shape, WidgetMessage::LinkWith(self.hold_slot)));
+				hold_shape
+			},
+			None => Handle::NONE,
+		};
 	}
 
 	fn update_piece_visibility(&self, ui: &UserInterface) {
@@ -427,8 +1534,31 @@ impl GamePlugin {
 			named_objects: false,
 		};
 
+		// `--seed <n>` replays a specific run, e.g. one quoted in a bug report
+		let seed = std::env::args().skip_while(|arg| arg != "--seed").nth(1).and_then(|seed| seed.parse().ok());
+		// `--size <columns>x<rows>` or `--size <n>` picks the board dimensions
+		let size = std::env::args().skip_while(|arg| arg != "--size").nth(1).and_then(|size| size.parse().ok());
+		// `--mode <name>` picks the game mode
+		let mode = std::env::args().skip_while(|arg| arg != "--mode").nth(1).and_then(|mode| mode.parse().ok());
+		// Puzzles are made for the default board, and everyone plays the daily challenge on it
+		let size = size.filter(|_| !matches!(mode, Some(GameMode::Puzzle | GameMode::Daily)));
+		// `--replay <path>` watches a recorded game, on a board of its size
+		let replay_file = std::env::args().skip_while(|arg| arg != "--replay").nth(1).and_then(|path| {
+			std::fs::read_to_string(&path)
+				.map_err(|err| err.to_string())
+				.and_then(|text| text.parse::<Replay>())
+				.inspect_err(|err| Log::err(format!("Can't load the replay {path}: {err}")))
+				.ok()
+		});
+
 		let mut executor = Executor::from_params(Some(event_loop), params);
-		executor.add_plugin(GamePlugin::default());
+		executor.add_plugin(GamePlugin {
+			seed,
+			size: replay_file.as_ref().map(|replay| replay.size).or(size).unwrap_or_default(),
+			mode: mode.unwrap_or_default(),
+			replay_file,
+			..Default::default()
+		});
 		executor.run();
 	}
 }
@@ -447,6 +1577,14 @@ impl Plugin for GamePlugin {
 			.user_interfaces
 			.add(UserInterface::new(Vector2::new(screen_size.0, screen_size.1)));
 
+		self.puzzles = Puzzle::bundled();
+		self.puzzle = storage::load(PUZZLE_KEY)
+			.and_then(|puzzle| puzzle.trim().parse().ok())
+			.filter(|&puzzle| puzzle < self.puzzles.len())
+			.unwrap_or_default();
+		self.daily = DailyHistory::load();
+		self.start_daily_attempt();
+
 		let ui = context.user_interfaces.first_mut();
 		let ui_root = ui.root();
 
@@ -456,7 +1594,108 @@ impl Plugin for GamePlugin {
 			ctx.link(root, ui_root);
 		}
 
+		self.high_scores = HighScores::load();
 		self.refresh(ui);
+		match self.replay_file.take() {
+			Some(replay) => self.start_replay(replay, ui),
+			None => self.offer_resume(ui),
+		}
+
+		Ok(())
+	}
+
+	fn update(&mut self, context: &mut PluginContext) -> GameResult {
+		let dt = context.dt;
+		let ui = context.user_interfaces.first_mut();
+		if let Some(replay) = self.replay.as_mut().filter(|replay| replay.playing) {
+			replay.timer -= dt * REPLAY_SPEEDS[replay.speed];
+			if replay.timer <= 0.0 {
+				replay.timer = REPLAY_MOVE_SECONDS / 2.0;
+				self.advance_replay(ui);
+			}
+		}
+		self.tick_clock(dt, ui);
+		Ok(())
+	}
+
+	fn on_deinit(&mut self, _context: PluginContext) -> GameResult {
+		self.autosave();
+		self.save_replay();
+		Ok(())
+	}
+
+	fn on_os_event(&mut self, event: &Event<()>, context: PluginContext) -> GameResult {
+		// Mobile platforms suspend the app when it goes to the background and may not resume it
+		if let Event::Suspended = event {
+			self.autosave();
+			self.set_clock_paused(true, context.user_interfaces.first_mut());
+		}
+		let Event::WindowEvent { event, .. } = event else {
+			return Ok(());
+		};
+
+		match event {
+			WindowEvent::ModifiersChanged(modifiers) => self.modifiers = modifiers.state(),
+			// Keep the game when the window closes or is hidden, a closing browser tab gives no other notice
+			// The clock of a blitz game waits for the player to come back
+			WindowEvent::CloseRequested | WindowEvent::Focused(false) | WindowEvent::Occluded(true) => {
+				self.autosave();
+				self.set_clock_paused(true, context.user_interfaces.first_mut());
+			},
+			WindowEvent::Focused(true) => self.set_clock_paused(false, context.user_interfaces.first_mut()),
+			// R rotates the piece in hand, or the tray piece under the cursor
+			WindowEvent::KeyboardInput {
+				event:
+					KeyEvent {
+						physical_key: PhysicalKey::Code(KeyCode::KeyR),
+						state: ElementState::Pressed,
+						..
+					},
+				..
+			} if self.state.is_some() => {
+				if let Some(piece_idx) = self.dragging.as_ref().map(|drag| drag.shape).or(self.hovered_piece) {
+					self.rotate_piece(piece_idx, context.user_interfaces.first_mut());
+				}
+			},
+			// H holds the piece in hand or under the cursor, or takes the held piece back
+			WindowEvent::KeyboardInput {
+				event:
+					KeyEvent {
+						physical_key: PhysicalKey::Code(KeyCode::KeyH),
+						state: ElementState::Pressed,
+						..
+					},
+				..
+			} if self.state.is_some() => {
+				let ui = context.user_interfaces.first_mut();
+				if let Some(drag) = self.dragging.as_mut() {
+					drag.over_hold = true;
+					self.end_drag(ui);
+				} else if let Some(piece_idx) = self.hovered_piece {
+					self.hold_piece(piece_idx, ui);
+				} else {
+					self.take_held_piece(ui);
+				}
+			},
+			// Ctrl+Z undoes, Ctrl+Shift+Z or Ctrl+Y redoes (Cmd instead of Ctrl on macOS)
+			WindowEvent::KeyboardInput {
+				event:
+					KeyEvent {
+						physical_key: PhysicalKey::Code(key @ (KeyCode::KeyZ | KeyCode::KeyY)),
+						state: ElementState::Pressed,
+						..
+					},
+				..
+			} if self.state.is_some() && (self.modifiers.control_key() || self.modifiers.super_key()) => {
+				let ui = context.user_interfaces.first_mut();
+				if *key == KeyCode::KeyZ && !self.modifiers.shift_key() {
+					self.undo(ui);
+				} else {
+					self.redo(ui);
+				}
+			},
+			_ => {},
+		}
 
 		Ok(())
 	}
@@ -474,6 +1713,36 @@ impl Plugin for GamePlugin {
 		let ui = context.user_interfaces.first_mut();
 		let dest = message.destination();
 
+		// Buttons of the overlays, undo and hint buttons and the replay controls in the header
+		if let Some(ButtonMessage::Click) = message.data() {
+			if dest == self.restart_button {
+				self.save_replay();
+				self.restart(ui);
+			} else if dest == self.next_puzzle_button {
+				self.next_puzzle(ui);
+			} else if dest == self.share_button {
+				self.share_daily(ui);
+			} else if dest == self.resume_button || dest == self.new_game_button {
+				self.resume(dest == self.resume_button, ui);
+			} else if dest == self.undo_button {
+				self.undo(ui);
+			} else if dest == self.hint_button {
+				self.show_hint(ui);
+			} else if dest == self.watch_replay_button {
+				let replay = Replay::from_game(self.mode, self.state.as_ref().unwrap());
+				self.start_replay(replay, ui);
+			} else if dest == self.replay_play_button {
+				self.toggle_replay(ui);
+			} else if dest == self.replay_step_button {
+				self.step_replay(ui);
+			} else if dest == self.replay_speed_button {
+				self.cycle_replay_speed(ui);
+			} else if dest == self.replay_exit_button {
+				self.stop_replay(ui);
+			}
+			return Ok(());
+		}
+
 		// Mouse down on piece - start drag
 		if let Some(WidgetMessage::MouseDown {
 			button: MouseButton::Left,
@@ -482,19 +1751,52 @@ impl Plugin for GamePlugin {
 		}) = message.data()
 		{
 			if let Some(piece_idx) = self.piece_widgets.iter().position(|&h| h == dest) {
-				self.start_drag(piece_idx, pos, ui);
+				self.start_drag(piece_idx, pos, None, ui);
+			} else if dest == self.hold_slot && self.dragging.is_none() {
+				self.take_held_piece(ui);
 			}
 			return Ok(());
 		}
 
-		// Touch start on piece - start drag
-		if let Some(WidgetMessage::TouchStarted { pos, .. }) = message.data() {
-			if let Some(piece_idx) = self.piece_widgets.iter().position(|&h| h == dest) {
-				self.start_drag(piece_idx, pos, ui);
+		// Right click - rotate the piece in hand, or the tray piece clicked
+		if let Some(WidgetMessage::MouseDown {
+			button: MouseButton::Right,
+			..
+		}) = message.data()
+		{
+			let piece_idx = match &self.dragging {
+				Some(drag) => Some(drag.shape),
+				None => self.piece_widgets.iter().position(|&h| h == dest),
+			};
+			if let Some(piece_idx) = piece_idx {
+				self.rotate_piece(piece_idx, ui);
 			}
 			return Ok(());
 		}
 
+		// Touch start - a second finger rotates the piece in hand, otherwise start drag on a piece
+		if let Some(WidgetMessage::TouchStarted { pos, id, .. }) = message.data() {
+			if let Some(drag) = &self.dragging {
+				if drag.touch != Some(*id) {
+					self.rotate_piece(drag.shape, ui);
+				}
+			} else if let Some(piece_idx) = self.piece_widgets.iter().position(|&h| h == dest) {
+				self.start_drag(piece_idx, pos, Some(*id), ui);
+			} else if dest == self.hold_slot {
+				self.take_held_piece(ui);
+			}
+			return Ok(());
+		}
+
+		// Track the tray piece under the cursor for the rotate key
+		if let Some(piece_idx) = self.piece_widgets.iter().position(|&h| h == dest) {
+			match message.data::<WidgetMessage>() {
+				Some(WidgetMessage::MouseEnter) => self.hovered_piece = Some(piece_idx),
+				Some(WidgetMessage::MouseLeave) => self.hovered_piece = None,
+				_ => {},
+			}
+		}
+
 		// Mouse move - update drag position
 		if let Some(WidgetMessage::MouseMove { pos, .. }) = message.data()
 			&& self.dragging.is_some()
@@ -504,8 +1806,8 @@ impl Plugin for GamePlugin {
 		}
 
 		// Touch move - update drag position and hover (no MouseEnter/Leave for touch)
-		if let Some(WidgetMessage::TouchMoved { pos, .. }) = message.data()
-			&& self.dragging.is_some()
+		if let Some(WidgetMessage::TouchMoved { pos, id, .. }) = message.data()
+			&& self.dragging.as_ref().is_some_and(|drag| drag.touch == Some(*id))
 		{
 			self.update_drag(pos, ui);
 			self.update_hover_from_pos(pos, ui);
@@ -516,7 +1818,7 @@ impl Plugin for GamePlugin {
 			&& let Some(ref mut drag) = self.dragging
 		{
 			if let Some(idx) = self.board_cells.iter().position(|&h| h == dest) {
-				drag.hover_cell = Some(Coord::from_index(idx));
+				drag.hover_cell = Some(Coord::from_index(idx, self.size));
 				self.refresh(ui);
 			}
 			return Ok(());
@@ -527,7 +1829,7 @@ impl Plugin for GamePlugin {
 			&& let Some(ref mut drag) = self.dragging
 		{
 			if let Some(idx) = self.board_cells.iter().position(|&h| h == dest)
-				&& drag.hover_cell == Some(Coord::from_index(idx))
+				&& drag.hover_cell == Some(Coord::from_index(idx, self.size))
 			{
 				drag.hover_cell = None;
 				self.refresh(ui);
@@ -545,16 +1847,16 @@ impl Plugin for GamePlugin {
 			self.end_drag(ui);
 		}
 
-		// Touch end - place shape
-		if let Some(WidgetMessage::TouchEnded { .. }) = message.data()
-			&& self.dragging.is_some()
+		// Touch end - place shape, unless it's the finger that rotated
+		if let Some(WidgetMessage::TouchEnded { id, .. }) = message.data()
+			&& self.dragging.as_ref().is_some_and(|drag| drag.touch == Some(*id))
 		{
 			self.end_drag(ui);
 		}
 
 		// Touch cancelled - cancel drag
-		if let Some(WidgetMessage::TouchCancelled { .. }) = message.data()
-			&& self.dragging.is_some()
+		if let Some(WidgetMessage::TouchCancelled { id, .. }) = message.data()
+			&& self.dragging.as_ref().is_some_and(|drag| drag.touch == Some(*id))
 		{
 			self.end_drag(ui);
 		}
@@ -0,0 +1,276 @@
+//! Hand-made challenges: a fixed board, a fixed run of pieces and a goal to reach with them.
+//!
+//! Puzzles are text files under `data/puzzles`. A header of `<key> <value>` lines comes first,
+//! `#` starting a comment, followed by the board and then every piece of the run in the notation of
+//! [`notation`](crate::notation):
+//!
+//! ```text
+//! name Warm-up
+//! goal lines 1
+//! moves 1
+//! solution 0:2,7
+//!
+//! [x x . . . . x x]
+//!
+//! [x x x x]
+//! ```
+//!
+//! `goal` is `lines <n>`, `empty` or `score <points>`. `moves` limits the placements and may be left out.
+//! `solution` lists moves that solve the puzzle in the format of replays, so the tests can check every puzzle.
+
+use std::{fmt, str::FromStr};
+
+use crate::{
+	generator::Sequence,
+	koala_kombo::{Board, KoalaKombo, Move, Shape},
+	mode::GameMode,
+	notation,
+};
+
+/// The puzzles that ship with the game, in the order they are played.
+const BUNDLED: &[&str] = &[
+	include_str!("../data/puzzles/01-warm-up.txt"),
+	include_str!("../data/puzzles/02-crossing.txt"),
+	include_str!("../data/puzzles/03-clean-sweep.txt"),
+	include_str!("../data/puzzles/04-double-cross.txt"),
+	include_str!("../data/puzzles/05-second-tray.txt"),
+];
+
+/// What a puzzle asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+	/// Clear at least this many rows and columns in total.
+	Lines(usize),
+	/// Leave no filled cell on the board.
+	EmptyBoard,
+	/// Reach at least this score.
+	Score(u32),
+}
+
+impl fmt::Display for Goal {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Goal::Lines(1) => write!(f, "Clear 1 line"),
+			Goal::Lines(lines) => write!(f, "Clear {lines} lines"),
+			Goal::EmptyBoard => write!(f, "Empty the board"),
+			Goal::Score(points) => write!(f, "Score {points} points"),
+		}
+	}
+}
+
+/// Parses the `goal` value of a puzzle file: `lines <n>`, `empty` or `score <points>`.
+impl FromStr for Goal {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let invalid = || format!("invalid goal `{s}`");
+		match s.split_whitespace().collect::<Vec<_>>().as_slice() {
+			["lines", lines] => Ok(Goal::Lines(lines.parse().map_err(|_| invalid())?)),
+			["empty"] => Ok(Goal::EmptyBoard),
+			["score", points] => Ok(Goal::Score(points.parse().map_err(|_| invalid())?)),
+			_ => Err(invalid()),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PuzzleStatus {
+	Playing,
+	Solved,
+	/// The moves ran out, or the pieces left don't fit anymore, before the goal was reached.
+	Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Puzzle {
+	pub name: String,
+	pub goal: Goal,
+	/// Most placements allowed, `None` for as many as the pieces last.
+	pub moves: Option<usize>,
+	board: Board,
+	shapes: Vec<Shape>,
+	solution: Vec<Move>,
+}
+
+impl Puzzle {
+	/// Returns the puzzles that ship with the game, in the order they are played.
+	pub fn bundled() -> Vec<Puzzle> {
+		BUNDLED.iter().map(|text| text.parse().expect("bundled puzzles are checked by the tests")).collect()
+	}
+
+	pub fn board(&self) -> &Board {
+		&self.board
+	}
+
+	/// The pieces the puzzle deals, three to a tray.
+	pub fn shapes(&self) -> &[Shape] {
+		&self.shapes
+	}
+
+	/// Builds the game at the start of the puzzle.
+	pub fn game(&self) -> KoalaKombo {
+		GameMode::Puzzle
+			.builder()
+			.with_board(self.board.clone())
+			.with_generator(Sequence::new(self.shapes.iter().copied()))
+			.with_seed(0)
+			.build()
+	}
+
+	/// Returns what's asked of the player, e.g. "Clear 4 lines in 3 moves".
+	pub fn objective(&self) -> String {
+		match self.moves {
+			Some(1) => format!("{} in 1 move", self.goal),
+			Some(moves) => format!("{} in {moves} moves", self.goal),
+			None => self.goal.to_string(),
+		}
+	}
+
+	/// Returns the number of rows and columns the game cleared since the start of the puzzle.
+	pub fn lines_cleared(&self, game: &KoalaKombo) -> usize {
+		let mut replay = self.game();
+		game
+			.moves()
+			.iter()
+			.filter_map(|mv| replay.place_shape(mv.piece_idx, mv.coord).ok())
+			.map(|outcome| outcome.clear.lines())
+			.sum()
+	}
+
+	/// Returns the number of placements left, `None` if the puzzle doesn't limit them.
+	pub fn moves_left(&self, game: &KoalaKombo) -> Option<usize> {
+		self.moves.map(|moves| moves.saturating_sub(game.moves().len()))
+	}
+
+	/// Returns how the game, started from [`Puzzle::game`], stands.
+	pub fn status(&self, game: &KoalaKombo) -> PuzzleStatus {
+		let solved = match self.goal {
+			Goal::Lines(lines) => self.lines_cleared(game) >= lines,
+			Goal::EmptyBoard => game.board().bits() == 0,
+			Goal::Score(points) => game.score >= points,
+		};
+
+		if solved {
+			PuzzleStatus::Solved
+		} else if game.is_game_over() || self.moves_left(game) == Some(0) {
+			PuzzleStatus::Failed
+		} else {
+			PuzzleStatus::Playing
+		}
+	}
+}
+
+/// Parses a puzzle file, see the [module documentation](self) for the format.
+impl FromStr for Puzzle {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let grids_start = s.lines().position(|line| line.trim_start().starts_with('[')).unwrap_or(s.lines().count());
+		let (mut name, mut goal, mut moves, mut solution) = (None, None, None, Vec::new());
+		for line in s.lines().take(grids_start).map(str::trim) {
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+			let (key, value) = line.split_once(' ').ok_or_else(|| format!("invalid line `{line}`"))?;
+			let value = value.trim();
+			match key {
+				"name" => name = Some(value.to_string()),
+				"goal" => goal = Some(value.parse()?),
+				"moves" => moves = Some(value.parse().map_err(|_| format!("invalid move limit `{value}`"))?),
+				"solution" => solution = value.split_whitespace().map(str::parse).collect::<Result<_, _>>()?,
+				_ => return Err(format!("unknown key `{key}`")),
+			}
+		}
+
+		let grids = notation::grids(&s.lines().skip(grids_start).collect::<Vec<_>>().join("\n"))?;
+		let (board, pieces) = grids.split_first().ok_or_else(|| String::from("puzzle has no board"))?;
+		let board = notation::board_from_grid(board)?;
+		let shapes = pieces
+			.iter()
+			.map(|grid| match notation::piece_from_grid(grid)? {
+				piece if piece.used => Err(String::from("puzzle pieces can't be used")),
+				piece => Ok(piece.shape),
+			})
+			.collect::<Result<Vec<_>, _>>()?;
+		if shapes.is_empty() {
+			return Err(String::from("puzzle has no pieces"));
+		}
+
+		let goal = goal.ok_or_else(|| String::from("puzzle has no goal"))?;
+		if goal == Goal::EmptyBoard && board.bits() == 0 {
+			return Err(String::from("the board is empty already"));
+		}
+		Ok(Self {
+			name: name.ok_or_else(|| String::from("puzzle has no name"))?,
+			goal,
+			moves,
+			board,
+			shapes,
+			solution,
+		})
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::koala_kombo::{BoardSize, Coord};
+
+	#[test]
+	fn bundled_test() {
+		// The window lays the board out once, so every puzzle plays on the default board
+		for (i, puzzle) in BUNDLED.iter().map(|text| text.parse::<Puzzle>()).enumerate() {
+			let puzzle = puzzle.unwrap_or_else(|err| panic!("puzzle {} doesn't parse: {err}", i + 1));
+			assert_eq!(puzzle.board.size(), BoardSize::default(), "{}", puzzle.name);
+
+			let mut game = puzzle.game();
+			assert_eq!(puzzle.status(&game), PuzzleStatus::Playing, "{}", puzzle.name);
+			for (j, mv) in puzzle.solution.iter().enumerate() {
+				assert_eq!(puzzle.status(&game), PuzzleStatus::Playing, "{} before move {}", puzzle.name, j + 1);
+				game.place_shape(mv.piece_idx, mv.coord).unwrap_or_else(|err| panic!("{}: {err}", puzzle.name));
+			}
+			assert_eq!(puzzle.status(&game), PuzzleStatus::Solved, "{}", puzzle.name);
+		}
+	}
+
+	#[test]
+	fn status_test() {
+		let puzzle = "
+			name Test
+			goal lines 1
+			moves 2
+
+			[x x x .]
+			[. . . .]
+			[. . . .]
+
+			[x]
+
+			[x]
+
+			[x x]
+
+			[x]
+		"
+		.parse::<Puzzle>()
+		.unwrap();
+		assert_eq!(puzzle.objective(), "Clear 1 line in 2 moves");
+		assert_eq!(puzzle.shapes(), [Shape::Single, Shape::Single, Shape::Duce, Shape::Single]);
+
+		let mut game = puzzle.game();
+		assert!(game.place_shape(0, Coord::new(3, 2)).is_ok());
+		assert_eq!(puzzle.moves_left(&game), Some(1));
+		assert!(game.place_shape(1, Coord::new(3, 1)).is_ok());
+		assert_eq!(puzzle.status(&game), PuzzleStatus::Failed);
+
+		// Undoing the move takes the failure back
+		assert!(game.undo());
+		assert!(game.place_shape(1, Coord::new(3, 0)).is_ok());
+		assert_eq!(puzzle.lines_cleared(&game), 1);
+		assert_eq!(puzzle.status(&game), PuzzleStatus::Solved);
+
+		assert!("name Test\ngoal empty\n\n[. .]\n\n[x]".parse::<Puzzle>().is_err());
+		assert!("name Test\ngoal lines 1\n\n[. .]".parse::<Puzzle>().is_err());
+		assert!("name Test\ngoal most\n\n[. .]\n\n[x]".parse::<Puzzle>().is_err());
+	}
+}
@@ -0,0 +1,285 @@
+//! Records of finished games that can be played back move by move.
+//!
+//! A replay only keeps what a game can't recompute: the seed, the board size, the mode and the placements.
+//! Everything else comes from replaying the moves, so a replay is only valid for the rules it was recorded with.
+
+use std::{fmt, str::FromStr};
+
+use crate::{
+	koala_kombo::{BoardSize, Coord, KoalaKombo, Move, PlacementError},
+	mode::GameMode,
+	storage,
+};
+
+/// Version of the rules a game is played with.
+/// Bump it whenever scoring, dealing, the shapes or the moves change, as older replays would play out differently.
+pub const RULES_VERSION: u32 = 2;
+
+/// Storage key of the replay of the last game played.
+const KEY: &str = "replay";
+
+/// First line of every replay, to tell it apart from other text.
+const HEADER: &str = "koala_kombo replay";
+
+/// Everything needed to play a game again exactly as it went.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay {
+	pub seed: u64,
+	pub rules_version: u32,
+	pub mode: GameMode,
+	pub size: BoardSize,
+	pub moves: Vec<Move>,
+}
+
+impl Replay {
+	/// Records the moves of a game played in the mode.
+	pub fn from_game(mode: GameMode, game: &KoalaKombo) -> Self {
+		Self {
+			seed: game.seed(),
+			rules_version: RULES_VERSION,
+			mode,
+			size: game.size(),
+			moves: game.moves().to_vec(),
+		}
+	}
+
+	/// Builds the game as it was before the first move.
+	pub fn game(&self) -> KoalaKombo {
+		self.mode.builder().with_size(self.size).with_seed(self.seed).build()
+	}
+
+	/// Plays every move and returns the finished game.
+	/// Replays don't record how long moves took, so the clock of a blitz game is never ticked.
+	/// Fails with the index of the first move that can't be played and why.
+	pub fn play(&self) -> Result<KoalaKombo, (usize, PlacementError)> {
+		let mut game = self.game();
+		for (i, &mv) in self.moves.iter().enumerate() {
+			game.play_move(mv).map_err(|err| (i, err))?;
+		}
+		Ok(game)
+	}
+
+	/// Loads the replay of the last game played, `None` if there is none or it can't be read anymore.
+	pub fn load() -> Option<Self> {
+		storage::load(KEY)?.parse().ok()
+	}
+
+	/// Keeps this as the replay of the last game played, finished or not.
+	pub fn save(&self) -> std::io::Result<()> {
+		storage::save(KEY, &self.to_string())
+	}
+}
+
+/// A header of `<key> <value>` lines followed by the moves, ten per line.
+impl fmt::Display for Replay {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(f, "{HEADER}")?;
+		writeln!(f, "rules {}", self.rules_version)?;
+		writeln!(f, "mode {}", self.mode)?;
+		writeln!(f, "size {}", self.size)?;
+		writeln!(f, "seed {}", self.seed)?;
+		writeln!(f, "moves {}", self.moves.len())?;
+		for line in self.moves.chunks(10) {
+			let line = line.iter().map(Move::to_string).collect::<Vec<_>>();
+			writeln!(f, "{}", line.join(" "))?;
+		}
+		Ok(())
+	}
+}
+
+/// A move as written in replays, `<piece>:<column>,<row>` or `h<piece>` for holding,
+/// followed by `+<turns>` if the piece was rotated.
+impl fmt::Display for Move {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if self.hold {
+			write!(f, "h{}", self.piece_idx)?;
+		} else {
+			write!(f, "{}:{},{}", self.piece_idx, self.coord.column, self.coord.row)?;
+		}
+		if self.turns > 0 {
+			write!(f, "+{}", self.turns)?;
+		}
+		Ok(())
+	}
+}
+
+impl FromStr for Move {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let parse = || {
+			let (s, turns) = s.split_once('+').unwrap_or((s, "0"));
+			let turns = turns.parse().ok()?;
+			if let Some(piece_idx) = s.strip_prefix('h') {
+				return Some(Move {
+					turns,
+					..Move::hold(piece_idx.parse().ok()?)
+				});
+			}
+			let (piece_idx, coord) = s.split_once(':')?;
+			let (column, row) = coord.split_once(',')?;
+			Some(Move {
+				piece_idx: piece_idx.parse().ok()?,
+				coord: Coord::new(column.parse().ok()?, row.parse().ok()?),
+				turns,
+				hold: false,
+			})
+		};
+		parse().ok_or_else(|| format!("invalid move `{s}`"))
+	}
+}
+
+/// Parses the format written by [`Display`](fmt::Display).
+/// Replays recorded with other rules are rejected, since they wouldn't play out the same.
+impl FromStr for Replay {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut lines = s.lines().map(str::trim).filter(|line| !line.is_empty());
+		if lines.next() != Some(HEADER) {
+			return Err("not a koala_kombo replay".to_string());
+		}
+
+		let mut field = |key: &str| {
+			lines
+				.next()
+				.and_then(|line| line.strip_prefix(key))
+				.and_then(|value| value.strip_prefix(' '))
+				.ok_or_else(|| format!("missing `{key}` line"))
+		};
+		let rules = field("rules")?;
+		let rules_version = rules.parse::<u32>().map_err(|_| format!("invalid rules version `{rules}`"))?;
+		if rules_version != RULES_VERSION {
+			return Err(format!("replay was recorded with rules {rules_version}, this game plays rules {RULES_VERSION}"));
+		}
+		let mode = field("mode")?.parse()?;
+		// Puzzles set up their own board and pieces, which a replay doesn't record
+		if mode == GameMode::Puzzle {
+			return Err(String::from("puzzle games can't be replayed"));
+		}
+		let size = field("size")?.parse()?;
+		let seed = field("seed")?;
+		let seed = seed.parse().map_err(|_| format!("invalid seed `{seed}`"))?;
+		let count = field("moves")?;
+		let count = count.parse::<usize>().map_err(|_| format!("invalid move count `{count}`"))?;
+
+		let moves = lines.flat_map(str::split_whitespace).map(str::parse).collect::<Result<Vec<_>, _>>()?;
+		if moves.len() != count {
+			return Err(format!("expected {count} moves, found {}", moves.len()));
+		}
+
+		Ok(Self {
+			seed,
+			rules_version,
+			mode,
+			size,
+			moves,
+		})
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::solver::Solver;
+
+	fn played(seed: u64) -> (KoalaKombo, Replay) {
+		let mut game = GameMode::Classic.builder().with_size(BoardSize::new(6, 5)).with_seed(seed).build();
+		let solver = Solver::default();
+		for _ in 0..30 {
+			if solver.play(&mut game) == 0 {
+				break;
+			}
+		}
+		let replay = Replay::from_game(GameMode::Classic, &game);
+		(game, replay)
+	}
+
+	#[test]
+	fn play_test() {
+		let (mut game, replay) = played(12);
+		assert!(!replay.moves.is_empty());
+		let replayed = replay.play().unwrap();
+		assert_eq!(replayed.board(), game.board());
+		assert_eq!(replayed.score, game.score);
+		assert_eq!(replayed.moves(), game.moves());
+
+		// Undone moves are not part of the replay
+		let last = *replay.moves.last().unwrap();
+		assert!(game.undo());
+		assert_eq!(game.moves(), &replay.moves[..replay.moves.len() - 1]);
+		assert!(game.redo());
+		assert_eq!(game.moves().last(), Some(&last));
+
+		let mut broken = replay.clone();
+		broken.moves.insert(0, broken.moves[0]);
+		assert_eq!(broken.play().unwrap_err(), (1, PlacementError::PieceAlreadyUsed));
+	}
+
+	#[test]
+	fn parse_test() {
+		let (_, replay) = played(3);
+		let text = replay.to_string();
+		assert!(text.starts_with("koala_kombo replay\nrules 2\nmode classic\nsize 6x5\nseed 3\n"));
+		assert_eq!(text.parse(), Ok(replay));
+
+		let other_rules = text.replace("rules 2", "rules 1");
+		assert!(other_rules.parse::<Replay>().is_err());
+		assert!(text.replace("mode classic", "mode puzzle").parse::<Replay>().is_err());
+		assert!(format!("{text}0:0,0\n").parse::<Replay>().is_err());
+		assert!(format!("{text}0-0-0\n").parse::<Replay>().is_err());
+		assert!("koala_kombo replay\nrules 2\n".parse::<Replay>().is_err());
+	}
+
+	#[test]
+	fn rotation_test() {
+		let mut game = GameMode::Twist.builder().with_size(BoardSize::new(6, 5)).with_seed(4).build();
+		let piece_idx = game.pieces.iter().position(|piece| piece.shape.rotations().len() > 1).unwrap();
+		assert!(game.rotate_piece(piece_idx));
+		let coord = game.size().coords().find(|&coord| game.fits(piece_idx, coord)).unwrap();
+		assert!(game.place_shape(piece_idx, coord).is_ok());
+
+		let replay = Replay::from_game(GameMode::Twist, &game);
+		assert_eq!(replay.moves[0].turns, 1);
+		assert!(replay.to_string().contains(&format!("{piece_idx}:{},{}+1", coord.column, coord.row)));
+		assert_eq!(replay.to_string().parse(), Ok(replay.clone()));
+		assert_eq!(replay.play().unwrap().board(), game.board());
+
+		// Games without rotation can't have played it
+		let classic = Replay {
+			mode: GameMode::Classic,
+			..replay
+		};
+		assert_eq!(classic.play().unwrap_err(), (0, PlacementError::CannotRotate));
+		assert!("1:2,3+".parse::<Move>().is_err());
+	}
+
+	#[test]
+	fn hold_test() {
+		let mut game = GameMode::Hold.builder().with_size(BoardSize::new(6, 5)).with_seed(8).build();
+		assert!(game.hold_piece(1));
+		assert!(Solver::default().play(&mut game) > 0);
+		assert!(game.hold_piece(1));
+
+		let replay = Replay::from_game(GameMode::Hold, &game);
+		assert_eq!(replay.moves[0], Move::hold(1));
+		assert!(replay.to_string().contains("\nh1 "));
+		assert_eq!(replay.to_string().parse(), Ok(replay.clone()));
+		let replayed = replay.play().unwrap();
+		assert_eq!(replayed.board(), game.board());
+		assert_eq!(replayed.held(), game.held());
+
+		let classic = Replay {
+			mode: GameMode::Classic,
+			..replay
+		};
+		assert_eq!(classic.play().unwrap_err(), (0, PlacementError::CannotHold));
+		assert_eq!(
+			"h2+1".parse(),
+			Ok(Move {
+				turns: 1,
+				..Move::hold(2)
+			})
+		);
+	}
+}
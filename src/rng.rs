@@ -0,0 +1,58 @@
+use std::convert::Infallible;
+
+#[cfg(feature = "gui")]
+use fyrox::core::visitor::prelude::*;
+use rand::{RngExt, TryRng, rand_core::utils};
+
+/// A small SplitMix64 generator owned by the game state.
+/// Every random decision of a game is drawn from it, so a run can be reproduced from its seed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameRng {
+	seed: u64,
+	state: u64,
+}
+
+impl GameRng {
+	pub fn new(seed: u64) -> Self {
+		Self { seed, state: seed }
+	}
+
+	/// Creates a generator with a seed picked from the thread-local entropy source.
+	pub fn from_entropy() -> Self {
+		Self::new(rand::rng().random())
+	}
+
+	/// Returns the seed this generator was created with.
+	pub fn seed(&self) -> u64 {
+		self.seed
+	}
+}
+
+impl TryRng for GameRng {
+	type Error = Infallible;
+
+	fn try_next_u32(&mut self) -> Result<u32, Infallible> {
+		self.try_next_u64().map(|val| (val >> 32) as u32)
+	}
+
+	fn try_next_u64(&mut self) -> Result<u64, Infallible> {
+		self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+		let mut z = self.state;
+		z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+		Ok(z ^ (z >> 31))
+	}
+
+	fn try_fill_bytes(&mut self, dst: &mut [u8]) -> Result<(), Infallible> {
+		utils::fill_bytes_via_next_word(dst, || self.try_next_u64())
+	}
+}
+
+#[cfg(feature = "gui")]
+impl Visit for GameRng {
+	fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+		let mut region = visitor.enter_region(name)?;
+		self.seed.visit("Seed", &mut region)?;
+		self.state.visit("State", &mut region)
+	}
+}
@@ -0,0 +1,133 @@
+//! Keeps the game in progress between launches.
+
+use fyrox::core::visitor::prelude::*;
+
+use crate::{koala_kombo::KoalaKombo, mode::GameMode, storage};
+
+/// Storage key of the saved game.
+const KEY: &str = "save";
+
+/// Serializes the game together with the mode it is played in.
+fn to_string(mode: GameMode, game: &mut KoalaKombo) -> Result<String, VisitError> {
+	let mut visitor = Visitor::new();
+	let mut name = mode.name().to_string();
+	name.visit("Mode", &mut visitor)?;
+	game.visit("Game", &mut visitor)?;
+	Ok(visitor.save_ascii_to_string())
+}
+
+/// Rebuilds a game serialized by [`to_string`], with the rules of its mode.
+fn from_str(data: &str) -> Result<(GameMode, KoalaKombo), VisitError> {
+	let mut visitor = Visitor::load_from_memory(data.as_bytes())?;
+	let mut name = String::new();
+	name.visit("Mode", &mut visitor)?;
+	let mode = name.parse::<GameMode>().map_err(VisitError::User)?;
+
+	let mut game = mode.builder().build();
+	game.visit("Game", &mut visitor)?;
+	Ok((mode, game))
+}
+
+/// Saves the game, replacing any earlier save.
+pub fn save_game(mode: GameMode, game: &mut KoalaKombo) -> VisitResult {
+	storage::save(KEY, &to_string(mode, game)?)?;
+	Ok(())
+}
+
+/// Loads the saved game, `None` if there is none or it can't be read anymore.
+pub fn load_game() -> Option<(GameMode, KoalaKombo)> {
+	from_str(&storage::load(KEY)?).ok()
+}
+
+pub fn delete_save() {
+	storage::remove(KEY);
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{
+		generator::Bag,
+		koala_kombo::{BoardSize, Coord, Piece, Shape},
+	};
+
+	#[test]
+	fn round_trip_test() {
+		let mut game = KoalaKombo::builder().with_size(BoardSize::new(6, 5)).with_seed(9).build();
+		game.pieces = [Piece {
+			shape: Shape::Single,
+			used: false,
+		}; 3];
+		assert!(game.place_shape(0, Coord::new(0, 0)).is_ok());
+		assert!(game.place_shape(1, Coord::new(5, 4)).is_ok());
+
+		let data = to_string(GameMode::Classic, &mut game).unwrap();
+		let (mode, mut loaded) = from_str(&data).unwrap();
+		assert_eq!(mode, GameMode::Classic);
+		assert_eq!(loaded.board(), game.board());
+		assert_eq!(loaded.pieces.map(|p| (p.shape, p.used)), game.pieces.map(|p| (p.shape, p.used)));
+		assert_eq!(loaded.score, game.score);
+		assert_eq!(loaded.seed(), 9);
+		assert!(!loaded.can_undo());
+
+		// The RNG carries on where it was, so both deal the same next tray
+		for game in [&mut game, &mut loaded] {
+			assert!(game.place_shape(2, Coord::new(3, 3)).unwrap().refilled);
+		}
+		assert_eq!(loaded.pieces.map(|p| p.shape), game.pieces.map(|p| p.shape));
+
+		assert!(from_str("not a save").is_err());
+	}
+
+	#[test]
+	fn obstacles_test() {
+		let mut game = GameMode::Obstacles.builder().with_seed(6).build();
+		assert_ne!(game.board().bits(), 0);
+
+		let data = to_string(GameMode::Obstacles, &mut game).unwrap();
+		let (mode, loaded) = from_str(&data).unwrap();
+		assert_eq!(mode, GameMode::Obstacles);
+		assert_eq!(loaded.board(), game.board());
+	}
+
+	#[test]
+	fn old_board_test() {
+		let mut game = GameMode::Classic.builder().with_seed(6).build();
+		game.place_shape(0, Coord::new(0, 0)).unwrap();
+		let data = to_string(GameMode::Classic, &mut game).unwrap();
+		let layers = "StonesLow<u64:0>StonesHigh<u64:0>IceLow<u64:0>IceHigh<u64:0>CrackedLow<u64:0>CrackedHigh<u64:0>";
+		assert!(data.contains(layers));
+
+		// Boards saved before obstacles load without any
+		let old = data.replace(layers, "").lines().filter(|line| !line.contains("Lock")).collect::<Vec<_>>().join("\n");
+		let (_, loaded) = from_str(&old).unwrap();
+		assert_eq!(loaded.board(), game.board());
+
+		// But a board missing only some of its layers is broken
+		assert!(from_str(&data.replace("IceLow<u64:0>", "")).is_err());
+	}
+
+	#[test]
+	fn bag_state_test() {
+		let mut game = KoalaKombo::builder().with_seed(4).with_generator(Bag::default()).build();
+		let mut visitor = Visitor::new();
+		game.visit("Game", &mut visitor).unwrap();
+		let data = visitor.save_ascii_to_string();
+
+		let mut loaded = KoalaKombo::builder().with_generator(Bag::default()).build();
+		let mut visitor = Visitor::load_from_memory(data.as_bytes()).unwrap();
+		loaded.visit("Game", &mut visitor).unwrap();
+
+		// A bag holds every shape once, so the shapes left in both bags must come out alike
+		for game in [&mut game, &mut loaded] {
+			game.pieces = [Piece {
+				shape: Shape::Single,
+				used: false,
+			}; 3];
+			for column in 0..3 {
+				assert!(game.place_shape(column, Coord::new(column, 0)).is_ok());
+			}
+		}
+		assert_eq!(loaded.pieces.map(|p| p.shape), game.pieces.map(|p| p.shape));
+	}
+}
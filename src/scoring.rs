@@ -0,0 +1,121 @@
+use std::fmt::Debug;
+
+#[cfg(feature = "gui")]
+use fyrox::core::visitor::prelude::*;
+
+use crate::koala_kombo::GRID_SIZE;
+
+/// Decides how many points a move is worth.
+/// Implement this to change the scoring of a game without touching the engine.
+pub trait ScoringRules: Debug + Send + Sync {
+	/// Points for putting `cells` blocks on the board, whether or not anything was cleared.
+	fn placement_points(&self, cells: usize) -> u32;
+
+	/// Points for clearing `lines` rows and columns with a single move while the combo streak is at `combo`.
+	/// `combo` is at least 1 because the clearing move itself counts toward the streak.
+	fn clear_points(&self, lines: usize, combo: u32) -> u32;
+
+	/// How many non-clearing moves in a row the combo streak survives before it resets.
+	fn combo_grace(&self) -> u32;
+}
+
+/// The default rules: a point per placed cell, and line points that multiply with both
+/// the number of lines cleared at once and the current combo streak.
+#[derive(Debug, Clone)]
+pub struct ClassicScoring {
+	pub cell_points: u32,
+	pub line_points: u32,
+	pub combo_grace: u32,
+}
+
+impl Default for ClassicScoring {
+	fn default() -> Self {
+		Self {
+			cell_points: 1,
+			line_points: GRID_SIZE as u32,
+			combo_grace: 3,
+		}
+	}
+}
+
+impl ScoringRules for ClassicScoring {
+	fn placement_points(&self, cells: usize) -> u32 {
+		cells as u32 * self.cell_points
+	}
+
+	fn clear_points(&self, lines: usize, combo: u32) -> u32 {
+		let lines = lines as u32;
+		// Two lines at once are worth twice as much per line, three lines three times, ...
+		self.line_points * lines * lines * combo.max(1)
+	}
+
+	fn combo_grace(&self) -> u32 {
+		self.combo_grace
+	}
+}
+
+/// Tracks consecutive clearing moves.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Combo {
+	/// Number of clearing moves in the current streak, `0` when there is no streak.
+	pub streak: u32,
+	/// Non-clearing moves since the last clear.
+	pub misses: u32,
+}
+
+impl Combo {
+	/// Records a move that cleared `lines` lines and returns the streak to score it with.
+	pub fn record(&mut self, lines: usize, grace: u32) -> u32 {
+		if lines > 0 {
+			self.streak += 1;
+			self.misses = 0;
+		} else if self.streak > 0 {
+			self.misses += 1;
+			if self.misses >= grace {
+				*self = Self::default();
+			}
+		}
+		self.streak
+	}
+}
+
+#[cfg(feature = "gui")]
+impl Visit for Combo {
+	fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+		let mut region = visitor.enter_region(name)?;
+		self.streak.visit("Streak", &mut region)?;
+		self.misses.visit("Misses", &mut region)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn classic_points_test() {
+		let rules = ClassicScoring::default();
+		assert_eq!(rules.placement_points(4), 4);
+		assert_eq!(rules.clear_points(0, 0), 0);
+		assert_eq!(rules.clear_points(1, 1), 8);
+		assert_eq!(rules.clear_points(2, 1), 32);
+		assert_eq!(rules.clear_points(1, 3), 24);
+	}
+
+	#[test]
+	fn combo_streak_test() {
+		let mut combo = Combo::default();
+		assert_eq!(combo.record(0, 2), 0);
+		assert_eq!(combo.record(1, 2), 1);
+		assert_eq!(combo.record(2, 2), 2);
+
+		// One miss is within the grace period
+		assert_eq!(combo.record(0, 2), 2);
+		assert_eq!(combo.record(1, 2), 3);
+
+		// Two misses in a row end the streak
+		assert_eq!(combo.record(0, 2), 3);
+		assert_eq!(combo.record(0, 2), 0);
+		assert_eq!(combo.record(1, 2), 1);
+	}
+}
@@ -0,0 +1,405 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use crate::koala_kombo::{Board, Coord, KoalaKombo, Move, Shape};
+
+/// Weighs what makes a board left behind by a tray good to keep playing on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heuristic {
+	/// Reward per row or column cleared along the way.
+	pub lines: f32,
+	/// Penalty per separate region of empty cells beyond the first.
+	pub fragmentation: f32,
+	/// Penalty per empty cell with no empty neighbour, which only a single can ever fill.
+	pub isolated: f32,
+	/// Reward per shape of [`Self::room_shapes`] that still fits somewhere.
+	pub room: f32,
+	/// Large shapes worth keeping room for.
+	pub room_shapes: Vec<Shape>,
+}
+
+impl Default for Heuristic {
+	fn default() -> Self {
+		Self {
+			lines: 10.0,
+			fragmentation: 4.0,
+			isolated: 3.0,
+			room: 6.0,
+			room_shapes: vec![Shape::Huge, Shape::LongHero, Shape::LongHeroUp],
+		}
+	}
+}
+
+impl Heuristic {
+	pub fn with_lines(mut self, weight: f32) -> Self {
+		self.lines = weight;
+		self
+	}
+
+	pub fn with_fragmentation(mut self, weight: f32) -> Self {
+		self.fragmentation = weight;
+		self
+	}
+
+	pub fn with_isolated(mut self, weight: f32) -> Self {
+		self.isolated = weight;
+		self
+	}
+
+	pub fn with_room(mut self, weight: f32, shapes: impl IntoIterator<Item = Shape>) -> Self {
+		self.room = weight;
+		self.room_shapes = shapes.into_iter().collect();
+		self
+	}
+
+	/// Scores a board reached after clearing `lines` rows and columns. Higher is better.
+	pub fn evaluate(&self, board: &Board, lines: usize) -> f32 {
+		let (regions, isolated) = empty_regions(board);
+		let room = self.room_shapes.iter().filter(|&&shape| board.fits_anywhere(shape)).count();
+
+		lines as f32 * self.lines - regions.saturating_sub(1) as f32 * self.fragmentation - isolated as f32 * self.isolated
+			+ room as f32 * self.room
+	}
+}
+
+/// The best found way to play the rest of the tray.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+	/// Placements in the order they have to be played with [`KoalaKombo::play_move`],
+	/// their turns counted from the way the pieces are turned now.
+	pub moves: Vec<Move>,
+	/// Rows and columns cleared over the whole plan.
+	pub lines_cleared: usize,
+	/// The board once every move is played.
+	pub board: Board,
+	/// Evaluation of [`Self::board`] by the solver's heuristic.
+	pub evaluation: f32,
+}
+
+impl Plan {
+	/// Returns if the plan places every unused piece of the tray.
+	pub fn is_complete(&self, game: &KoalaKombo) -> bool {
+		self.slots_left(game) == 0
+	}
+
+	/// Number of tray slots the plan leaves unused.
+	fn slots_left(&self, game: &KoalaKombo) -> usize {
+		let mut used = game.pieces.map(|piece| piece.used);
+		for mv in self.moves.iter().filter(|mv| !mv.hold) {
+			used[mv.piece_idx] = true;
+		}
+		used.iter().filter(|&&used| !used).count()
+	}
+
+	/// Number of pieces the plan places, the held one included.
+	fn placements(&self) -> usize {
+		self.moves.iter().filter(|mv| !mv.hold).count()
+	}
+}
+
+/// Searches every order, position and allowed orientation for the unused tray pieces,
+/// and for the held piece swapped into the tray in games with a hold slot.
+#[derive(Debug, Clone, Default)]
+pub struct Solver {
+	heuristic: Heuristic,
+}
+
+impl Solver {
+	pub fn new(heuristic: Heuristic) -> Self {
+		Self { heuristic }
+	}
+
+	pub fn heuristic(&self) -> &Heuristic {
+		&self.heuristic
+	}
+
+	/// Plans the rest of the tray, `None` if not even one piece fits.
+	///
+	/// Plans using up more of the tray always win, then those placing more pieces,
+	/// so a partial plan is only returned when no order fits the whole tray.
+	pub fn plan(&self, game: &KoalaKombo) -> Option<Plan> {
+		let shapes = game.pieces.map(|piece| (!piece.used).then_some(piece.shape));
+		let mut search = Search {
+			heuristic: &self.heuristic,
+			game,
+			shapes,
+			held: game.held(),
+			moves: Vec::with_capacity(3),
+			visited: HashMap::new(),
+			best: None,
+		};
+		search.run(game.board(), 0);
+		search.best
+	}
+
+	/// Plays the planned moves on the game. Returns the number of moves played.
+	pub fn play(&self, game: &mut KoalaKombo) -> usize {
+		let Some(plan) = self.plan(game) else {
+			return 0;
+		};
+		plan.moves.iter().take_while(|&&mv| game.play_move(mv).is_ok()).count()
+	}
+}
+
+struct Search<'a> {
+	heuristic: &'a Heuristic,
+	/// The game planned for, whose rules say how pieces may be turned.
+	game: &'a KoalaKombo,
+	/// Shapes still to place, `None` for used or already placed slots.
+	shapes: [Option<Shape>; 3],
+	/// The piece in the hold slot.
+	held: Option<Shape>,
+	moves: Vec<Move>,
+	/// Boards already searched together with the slots that were left to place and the held piece,
+	/// and the most lines cleared on the way to them.
+	visited: HashMap<(Board, [bool; 3], Option<Shape>), usize>,
+	best: Option<Plan>,
+}
+
+impl Search<'_> {
+	fn run(&mut self, board: &Board, lines: usize) {
+		if !self.moves.is_empty() {
+			self.consider(board, lines);
+		}
+
+		for piece_idx in 0..self.shapes.len() {
+			let Some(shape) = self.shapes[piece_idx] else {
+				continue;
+			};
+			// Equal shapes lead to the same boards, so only the first of them is tried at each step
+			if self.shapes[..piece_idx].contains(&Some(shape)) {
+				continue;
+			}
+
+			self.shapes[piece_idx] = None;
+			self.place(board, lines, piece_idx, shape);
+			self.shapes[piece_idx] = Some(shape);
+		}
+
+		// The held piece is swapped into a slot and placed from there, unless the tray is used up and dealt again
+		let Some(held) = self.held else {
+			return;
+		};
+		if self.shapes.iter().all(Option::is_none) {
+			return;
+		}
+		for slot in 0..self.shapes.len() {
+			if self.shapes[..slot].contains(&self.shapes[slot]) {
+				continue;
+			}
+
+			let parked = self.shapes[slot].take();
+			self.held = parked;
+			self.moves.push(Move::hold(slot));
+			self.place(board, lines, slot, held);
+			self.moves.pop();
+			self.held = Some(held);
+			self.shapes[slot] = parked;
+		}
+	}
+
+	/// Searches on from every placement of the shape from the slot, in each orientation the rules allow.
+	fn place(&mut self, board: &Board, lines: usize, piece_idx: usize, shape: Shape) {
+		for (turns, turned) in self.game.orientations(shape).into_iter().enumerate() {
+			for coord in board.size().coords() {
+				let Some(mask) = board.placement_mask(turned, coord) else {
+					continue;
+				};
+				if mask & board.bits() != 0 {
+					continue;
+				}
+
+				let mut next = board.clone();
+				next.fill(mask);
+				let cleared = lines + next.clear_lines().lines();
+				// Another order may reach the same board, it is only searched again if it cleared more on the way
+				let key = (next.clone(), self.shapes.map(|shape| shape.is_some()), self.held);
+				if self.visited.get(&key).is_some_and(|&best| best >= cleared) {
+					continue;
+				}
+				self.visited.insert(key, cleared);
+
+				self.moves.push(Move {
+					turns: turns as u8,
+					..Move::new(piece_idx, coord)
+				});
+				self.run(&next, cleared);
+				self.moves.pop();
+			}
+		}
+	}
+
+	fn consider(&mut self, board: &Board, lines: usize) {
+		let rank = (Reverse(self.shapes.iter().flatten().count()), self.moves.iter().filter(|mv| !mv.hold).count());
+		let best_rank = self.best.as_ref().map(|best| (Reverse(best.slots_left(self.game)), best.placements()));
+		if best_rank.is_some_and(|best_rank| best_rank > rank) {
+			return;
+		}
+
+		let evaluation = self.heuristic.evaluate(board, lines);
+		let better = match self.best {
+			Some(ref best) => best_rank < Some(rank) || evaluation > best.evaluation,
+			None => true,
+		};
+		if better {
+			self.best = Some(Plan {
+				moves: self.moves.clone(),
+				lines_cleared: lines,
+				board: board.clone(),
+				evaluation,
+			});
+		}
+	}
+}
+
+/// Counts the separate regions of empty cells and the empty cells with no empty neighbour.
+fn empty_regions(board: &Board) -> (usize, usize) {
+	let size = board.size();
+	let neighbours = |c: Coord| {
+		[
+			c.column.checked_sub(1).map(|column| Coord::new(column, c.row)),
+			c.row.checked_sub(1).map(|row| Coord::new(c.column, row)),
+			c.offset(1, 0, size),
+			c.offset(0, 1, size),
+		]
+		.into_iter()
+		.flatten()
+		.filter(|&n| !board.filled(n))
+	};
+
+	let mut seen = vec![false; size.cells()];
+	let mut regions = 0;
+	let mut isolated = 0;
+	let mut stack = Vec::new();
+	for start in size.coords().filter(|&c| !board.filled(c)) {
+		if neighbours(start).next().is_none() {
+			isolated += 1;
+		}
+		if seen[start.to_index(size)] {
+			continue;
+		}
+
+		regions += 1;
+		seen[start.to_index(size)] = true;
+		stack.push(start);
+		while let Some(cell) = stack.pop() {
+			for n in neighbours(cell) {
+				if !seen[n.to_index(size)] {
+					seen[n.to_index(size)] = true;
+					stack.push(n);
+				}
+			}
+		}
+	}
+
+	(regions, isolated)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::koala_kombo::{BoardSize, Piece};
+
+	#[test]
+	fn plan_order_test() {
+		// Only row 3 is empty: the 3x3 fits once the 1x4 has cleared the whole board
+		let mut board = Board::new(BoardSize::square(4));
+		for coord in board.size().coords().filter(|c| c.row < 3) {
+			board.set(coord, true);
+		}
+		let mut game = KoalaKombo::builder().with_board(board).build();
+		game.pieces = [Shape::Single, Shape::Huge, Shape::Hero].map(|shape| Piece { shape, used: false });
+
+		let solver = Solver::default();
+		let plan = solver.plan(&game).unwrap();
+		assert!(plan.is_complete(&game));
+		assert_eq!(plan.moves[0], Move::new(2, Coord::new(0, 3)));
+		assert_eq!(plan.lines_cleared, 9);
+
+		assert_eq!(solver.play(&mut game), 3);
+		assert!(game.pieces.iter().all(|piece| !piece.used));
+	}
+
+	#[test]
+	fn partial_plan_test() {
+		let mut board = Board::default();
+		for coord in board.size().coords().filter(|c| (c.column + c.row) % 2 == 1) {
+			board.set(coord, true);
+		}
+		let mut game = KoalaKombo::builder().with_board(board).build();
+		game.pieces = [Shape::Single, Shape::Huge, Shape::Single].map(|shape| Piece { shape, used: false });
+
+		let plan = Solver::default().plan(&game).unwrap();
+		assert!(!plan.is_complete(&game));
+		assert_eq!(plan.moves.len(), 2);
+		assert!(plan.moves.iter().all(|mv| mv.piece_idx != 1));
+
+		game.pieces[0].used = true;
+		game.pieces[2].used = true;
+		assert_eq!(Solver::default().plan(&game), None);
+	}
+
+	#[test]
+	fn rotation_plan_test() {
+		// Only column 3 is empty, which a 1x4 only fits once it is turned upright
+		let mut board = Board::new(BoardSize::square(4));
+		for coord in board.size().coords().filter(|c| c.column < 3) {
+			board.set(coord, true);
+		}
+		let pieces = [Shape::Hero; 3].map(|shape| Piece { shape, used: false });
+		let mut game = KoalaKombo::builder().with_board(board.clone()).build();
+		game.pieces = pieces;
+		assert_eq!(Solver::default().plan(&game), None);
+
+		let mut game = KoalaKombo::builder().with_board(board).with_rotation(true).build();
+		game.pieces = pieces;
+		let plan = Solver::default().plan(&game).unwrap();
+		assert!(plan.is_complete(&game));
+		assert_eq!(plan.moves[0].turns, 1);
+		assert_eq!(Solver::default().play(&mut game), 3);
+	}
+
+	#[test]
+	fn hold_plan_test() {
+		let board = Board::new(BoardSize::new(4, 1));
+		let mut game = KoalaKombo::builder().with_board(board).with_hold(true).build();
+		game.pieces = [Shape::Hero, Shape::Huge, Shape::Huge].map(|shape| Piece { shape, used: false });
+		assert!(game.hold_piece(0));
+
+		// Parking a 3x3 for the 1x4 leaves less of the tray to place than swapping into the empty slot
+		let plan = Solver::default().plan(&game).unwrap();
+		assert_eq!(plan.moves, [Move::hold(1), Move::new(1, Coord::new(0, 0))]);
+		assert!(!plan.is_complete(&game));
+		assert_eq!(Solver::default().play(&mut game), 2);
+		assert_eq!(game.held(), Some(Shape::Huge));
+	}
+
+	#[test]
+	fn heuristic_test() {
+		let heuristic = Heuristic::default();
+		let empty = Board::default();
+
+		// Two filled cells splitting off a corner are worse than two next to each other
+		let mut split = Board::default();
+		split.set(Coord::new(1, 0), true);
+		split.set(Coord::new(0, 1), true);
+		let mut compact = Board::default();
+		compact.set(Coord::new(0, 0), true);
+		compact.set(Coord::new(1, 0), true);
+		assert_eq!(empty_regions(&split), (2, 1));
+		assert_eq!(empty_regions(&compact), (1, 0));
+		assert!(heuristic.evaluate(&compact, 0) > heuristic.evaluate(&split, 0));
+		assert!(heuristic.evaluate(&empty, 1) > heuristic.evaluate(&empty, 0));
+
+		// Blocking the only spots large shapes fit costs the room reward
+		let mut crowded = Board::default();
+		for coord in crowded.size().coords().filter(|c| c.row % 3 == 2) {
+			crowded.set(coord, true);
+		}
+		let no_room = heuristic.clone().with_room(0.0, []);
+		assert!(heuristic.evaluate(&crowded, 0) < heuristic.evaluate(&empty, 0));
+		assert_eq!(
+			heuristic.evaluate(&empty, 0) - no_room.evaluate(&empty, 0),
+			heuristic.room * heuristic.room_shapes.len() as f32
+		);
+	}
+}
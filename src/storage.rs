@@ -0,0 +1,83 @@
+//! A small key-value store for data that outlives a session.
+//! Native builds keep one file per key in the user's data directory, the browser build uses `localStorage`.
+
+use std::io;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{fs, path::PathBuf};
+
+/// Directory the native build stores its files in.
+/// `KOALA_KOMBO_DATA_DIR` overrides the platform default, e.g. for portable installs.
+/// Without a home directory the files go to the temp directory rather than wherever the game was started from.
+#[cfg(not(target_arch = "wasm32"))]
+fn data_dir() -> PathBuf {
+	let env = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+	if let Some(dir) = env("KOALA_KOMBO_DATA_DIR") {
+		return dir;
+	}
+
+	let base = if cfg!(windows) {
+		env("APPDATA")
+	} else if cfg!(target_os = "macos") {
+		env("HOME").map(|home| home.join("Library/Application Support"))
+	} else {
+		env("XDG_DATA_HOME").or_else(|| env("HOME").map(|home| home.join(".local/share")))
+	};
+	base.unwrap_or_else(std::env::temp_dir).join("koala_kombo")
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn path(key: &str) -> PathBuf {
+	data_dir().join(format!("{key}.txt"))
+}
+
+/// Returns the value stored under `key`, `None` if there is none.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load(key: &str) -> Option<String> {
+	fs::read_to_string(path(key)).ok()
+}
+
+/// Stores `value` under `key`, replacing what was there.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save(key: &str, value: &str) -> io::Result<()> {
+	fs::create_dir_all(data_dir())?;
+	fs::write(path(key), value)
+}
+
+/// Forgets the value stored under `key`, if any.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn remove(key: &str) {
+	let _ = fs::remove_file(path(key));
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+	web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn item(key: &str) -> String {
+	format!("koala_kombo/{key}")
+}
+
+/// Returns the value stored under `key`, `None` if there is none.
+#[cfg(target_arch = "wasm32")]
+pub fn load(key: &str) -> Option<String> {
+	local_storage()?.get_item(&item(key)).ok()?
+}
+
+/// Stores `value` under `key`, replacing what was there.
+#[cfg(target_arch = "wasm32")]
+pub fn save(key: &str, value: &str) -> io::Result<()> {
+	local_storage()
+		.and_then(|storage| storage.set_item(&item(key), value).ok())
+		.ok_or_else(|| io::Error::other("localStorage is not available"))
+}
+
+/// Forgets the value stored under `key`, if any.
+#[cfg(target_arch = "wasm32")]
+pub fn remove(key: &str) {
+	if let Some(storage) = local_storage() {
+		let _ = storage.remove_item(&item(key));
+	}
+}